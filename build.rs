@@ -3,6 +3,7 @@ use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::path::Path;
+use std::process::Command;
 use walkdir::WalkDir;
 
 const RENAMED_SYNTAXRULES: &[(&str, &str, &str)] = &[
@@ -119,8 +120,8 @@ fn write_config_rules_rs(
     let _ = writeln!(o, "pub struct ConfigTextRules {{");
 
     for (rulename, _) in textrules {
-        let _ = writeln!(o, "    #[serde(default = \"default_as_false\")]");
-        let _ = writeln!(o, "    pub {}: bool,", rulename);
+        let _ = writeln!(o, "    #[serde(default = \"default_as_off\")]");
+        let _ = writeln!(o, "    pub {}: Severity,", rulename);
     }
 
     let _ = writeln!(o, "}}");
@@ -131,8 +132,8 @@ fn write_config_rules_rs(
     let _ = writeln!(o, "pub struct ConfigSyntaxRules {{");
 
     for (rulename, _) in syntaxrules {
-        let _ = writeln!(o, "    #[serde(default = \"default_as_false\")]");
-        let _ = writeln!(o, "    pub {}: bool,", rulename);
+        let _ = writeln!(o, "    #[serde(default = \"default_as_off\")]");
+        let _ = writeln!(o, "    pub {}: Severity,", rulename);
     }
 
     let _ = writeln!(o, "");
@@ -140,12 +141,19 @@ fn write_config_rules_rs(
     for (original_rulename, _, _) in RENAMED_SYNTAXRULES {
         let _ = writeln!(
             o,
-            "    #[serde(default = \"default_as_false\", skip_serializing)]"
+            "    #[serde(default = \"default_as_off\", skip_serializing)]"
         );
-        let _ = writeln!(o, "    pub {}: bool,", original_rulename);
+        let _ = writeln!(o, "    pub {}: Severity,", original_rulename);
     }
 
     let _ = writeln!(o, "}}");
+
+    let _ = writeln!(o);
+    let _ = writeln!(o, "pub const RENAMED_SYNTAXRULES: &[&str] = &[");
+    for (original_rulename, _, _) in RENAMED_SYNTAXRULES {
+        let _ = writeln!(o, "    \"{}\",", original_rulename);
+    }
+    let _ = writeln!(o, "];");
 }
 
 fn write_impl_config_rs(
@@ -166,50 +174,64 @@ fn write_impl_config_rs(
     let _ = writeln!(o, "");
     let _ = writeln!(o, "    pub fn enable_all(mut self) -> Self {{");
     for (rulename, _) in textrules {
-        let _ = writeln!(o, "        self.textrules.{} = true;", rulename);
+        let _ = writeln!(o, "        self.textrules.{} = Severity::Error;", rulename);
     }
     for (rulename, _) in syntaxrules {
-        let _ = writeln!(o, "        self.syntaxrules.{} = true;", rulename);
+        let _ = writeln!(o, "        self.syntaxrules.{} = Severity::Error;", rulename);
     }
     let _ = writeln!(o, "");
     let _ = writeln!(o, "        self");
     let _ = writeln!(o, "    }}");
 
-    // `gen_textrules()` used in `src/linter.rs` to gather rules to load.
+    // `gen_textrules()` gathers enabled rules, without their severities.
     let _ = writeln!(o, "");
     let _ = writeln!(o, "    pub fn gen_textrules(&self) -> Vec<Box<dyn TextRule>> {{");
-    let _ = writeln!(o, "        let mut ret: Vec<Box<dyn TextRule>> = Vec::new();");
+    let _ = writeln!(o, "        self.gen_textrules_with_severity().into_iter().map(|(x, _)| x).collect()");
+    let _ = writeln!(o, "    }}");
+
+    // `gen_textrules_with_severity()` used in `src/linter.rs` to gather rules
+    // to load.
+    let _ = writeln!(o);
+    let _ = writeln!(o, "    pub fn gen_textrules_with_severity(&self) -> Vec<(Box<dyn TextRule>, Severity)> {{");
+    let _ = writeln!(o, "        let mut ret: Vec<(Box<dyn TextRule>, Severity)> = Vec::new();");
     for (rulename, structname) in textrules {
-        let _ = writeln!(o, "        if self.textrules.{} {{", rulename);
+        let _ = writeln!(o, "        if self.textrules.{} != Severity::Off {{", rulename);
         let _ = writeln!(
             o,
-            "            ret.push(Box::new({}::default()));",
-            structname
+            "            ret.push((Box::new({}::default()), self.textrules.{}));",
+            structname, rulename
         );
         let _ = writeln!(o, "        }}");
     }
     let _ = writeln!(o, "        ret");
     let _ = writeln!(o, "    }}");
 
-    // `gen_syntaxrules()` used in `src/linter.rs` to gather rules to load.
+    // `gen_syntaxrules()` gathers enabled rules, without their severities.
     let _ = writeln!(o, "");
     let _ = writeln!(o, "    pub fn gen_syntaxrules(&self) -> Vec<Box<dyn SyntaxRule>> {{");
-    let _ = writeln!(o, "        let mut ret: Vec<Box<dyn SyntaxRule>> = Vec::new();");
+    let _ = writeln!(o, "        self.gen_syntaxrules_with_severity().into_iter().map(|(x, _)| x).collect()");
+    let _ = writeln!(o, "    }}");
+
+    // `gen_syntaxrules_with_severity()` used in `src/linter.rs` to gather
+    // rules to load.
+    let _ = writeln!(o);
+    let _ = writeln!(o, "    pub fn gen_syntaxrules_with_severity(&self) -> Vec<(Box<dyn SyntaxRule>, Severity)> {{");
+    let _ = writeln!(o, "        let mut ret: Vec<(Box<dyn SyntaxRule>, Severity)> = Vec::new();");
     for (rulename, structname) in syntaxrules {
-        let _ = writeln!(o, "        if self.syntaxrules.{} {{", rulename);
+        let _ = writeln!(o, "        if self.syntaxrules.{} != Severity::Off {{", rulename);
         let _ = writeln!(
             o,
-            "            ret.push(Box::new({}::default()));",
-            structname
+            "            ret.push((Box::new({}::default()), self.syntaxrules.{}));",
+            structname, rulename
         );
         let _ = writeln!(o, "        }}");
     }
     for (original_rulename, _, structname) in RENAMED_SYNTAXRULES {
-        let _ = writeln!(o, "        if self.syntaxrules.{} {{", original_rulename);
+        let _ = writeln!(o, "        if self.syntaxrules.{} != Severity::Off {{", original_rulename);
         let _ = writeln!(
             o,
-            "            ret.push(Box::new({}::default()));",
-            structname
+            "            ret.push((Box::new({}::default()), self.syntaxrules.{}));",
+            structname, original_rulename
         );
         let _ = writeln!(o, "        }}");
     }
@@ -251,7 +273,7 @@ fn write_impl_config_rs(
         "        let mut ret: Vec<(String, String)> = Vec::new();"
     );
     for (original_rulename, rulename, _) in RENAMED_SYNTAXRULES {
-        let _ = writeln!(o, "        if self.syntaxrules.{} {{", original_rulename);
+        let _ = writeln!(o, "        if self.syntaxrules.{} != Severity::Off {{", original_rulename);
         let _ = writeln!(
             o,
             "            ret.push((String::from(\"{}\"), String::from(\"{}\")));",
@@ -408,6 +430,92 @@ fn write_test_rs(
     }
 }
 
+fn write_rule_docs_rs(
+    textrules: &Vec<(String, String)>,
+    syntaxrules: &Vec<(String, String)>,
+) {
+    let root_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let o = Path::new(&out_dir).join("rule_docs.rs");
+    let mut o = File::create(&o).unwrap();
+
+    // Options are found by their use in each rule's source, where the
+    // `ConfigOption` argument is always called `option`.
+    let re_option = Regex::new(r"option\.([a-z0-9_]+)").unwrap();
+
+    let _ = writeln!(o, "pub const RULE_DOCS: &[RuleDoc] = &[");
+    for (ruletype, rules) in [("text", textrules), ("syntax", syntaxrules)] {
+        for (rulename, _) in rules {
+            let mut src = String::new();
+            let src_path = format!("src/{}rules/{}.rs", ruletype, rulename);
+            let _ = File::open(src_path).unwrap().read_to_string(&mut src);
+            let mut options: Vec<String> = re_option
+                .captures_iter(&src)
+                .map(|x| format!("{:?}", &x[1]))
+                .collect();
+            options.sort();
+            options.dedup();
+
+            let path = |x: String| Path::new(&root_dir).join(x).to_string_lossy().to_string();
+            let explanation = path(format!("md/{}rules-explanation-{}.md", ruletype, rulename));
+            let pass = path(format!("testcases/{}rules/pass/{}.sv", ruletype, rulename));
+            let fail = path(format!("testcases/{}rules/fail/{}.sv", ruletype, rulename));
+
+            let _ = writeln!(o, "    RuleDoc {{");
+            let _ = writeln!(o, "        name: \"{}\",", rulename);
+            let _ = writeln!(o, "        ruletype: \"{}\",", ruletype);
+            let _ = writeln!(o, "        explanation: include_str!({:?}),", explanation);
+            let _ = writeln!(o, "        pass: include_str!({:?}),", pass);
+            let _ = writeln!(o, "        fail: include_str!({:?}),", fail);
+            let _ = writeln!(o, "        options: &[{}],", options.join(", "));
+            let _ = writeln!(o, "    }},");
+        }
+    }
+    let _ = writeln!(o, "];");
+}
+
+fn write_rulesets_rs() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let o = Path::new(&out_dir).join("rulesets.rs");
+    let mut o = File::create(&o).unwrap();
+
+    let re = Regex::new(r"ruleset-([a-zA-Z0-9_-]+)\.md$").unwrap();
+    let mut rulesets = Vec::new();
+    for entry in WalkDir::new("md").max_depth(1) {
+        let entry = entry.unwrap();
+        let path = entry.path().to_string_lossy().to_string();
+        if let Some(caps) = re.captures(&path) {
+            let name = String::from(&caps[1]);
+            let mut s = String::new();
+            let _ = File::open(entry.path()).unwrap().read_to_string(&mut s);
+
+            // Only the contents of ```toml blocks are configuration, the same
+            // as in the `rulesets/*.toml` files written by mdgen.
+            let mut toml = String::new();
+            let mut in_toml = false;
+            for line in s.lines() {
+                if line.starts_with("```toml") {
+                    in_toml = true;
+                } else if line.starts_with("```") {
+                    in_toml = false;
+                } else if in_toml {
+                    toml.push_str(line);
+                    toml.push('\n');
+                }
+            }
+            rulesets.push((name, toml));
+        }
+    }
+
+    rulesets.sort_by_key(|a| a.0.to_lowercase());
+
+    let _ = writeln!(o, "pub const RULESETS: &[(&str, &str)] = &[");
+    for (name, toml) in &rulesets {
+        let _ = writeln!(o, "    ({:?}, {:?}),", name, toml);
+    }
+    let _ = writeln!(o, "];");
+}
+
 fn main() {
     let re_struct = Regex::new(r"pub struct ([a-zA-Z0-9]*)").unwrap();
 
@@ -453,4 +561,51 @@ fn main() {
     write_config_rules_rs(&textrules, &syntaxrules);
     write_impl_config_rs(&textrules, &syntaxrules);
     write_test_rs(&textrules, &syntaxrules);
+    write_rulesets_rs();
+    write_rule_docs_rs(&textrules, &syntaxrules);
+
+    // Plugins are only compatible with svlint when built by the same compiler.
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .map(|x| String::from_utf8_lossy(&x.stdout).trim().to_string())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=SVLINT_RUSTC_VERSION={}", rustc_version);
+
+    // Nor with another version of sv-parser, whose types are shared too.
+    println!("cargo:rustc-env=SVLINT_SV_PARSER_VERSION={}", sv_parser_version());
+}
+
+// Version of sv-parser resolved for this build, from the lock file of the
+// workspace being built, i.e. svlint's own or that of a plugin, which is found
+// above the output directory unless the target directory is elsewhere.
+fn sv_parser_version() -> String {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let dirs = Path::new(&out_dir).ancestors().chain(Path::new(&manifest_dir).ancestors());
+    for dir in dirs {
+        let mut s = String::new();
+        let read = File::open(dir.join("Cargo.lock")).and_then(|mut f| f.read_to_string(&mut s));
+        if read.is_err() {
+            continue;
+        }
+
+        // Each package has its name before its version.
+        let mut name = "";
+        let mut versions = Vec::new();
+        for line in s.lines() {
+            if let Some(x) = line.strip_prefix("name = ") {
+                name = x;
+            } else if let Some(x) = line.strip_prefix("version = ") {
+                if name == "\"sv-parser\"" {
+                    versions.push(x.trim_matches('"'));
+                }
+            }
+        }
+        if !versions.is_empty() {
+            return versions.join(",");
+        }
+    }
+    String::new()
 }
@@ -0,0 +1,163 @@
+use crate::linter::{read_source, LintFailed};
+use anyhow::{Context, Error};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+// Location-tolerant identity of a rule failure, which is unaffected by edits
+// elsewhere in the file, i.e. lines being added or removed above it.
+// Where the same rule fails several times on lines with the same text, the
+// occurrence index distinguishes each failure, in order of appearance.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Fingerprint {
+    pub path: String,
+    pub rule: String,
+    pub source: String,
+    pub occurrence: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct BaselineFile {
+    version: usize,
+    failures: Vec<Fingerprint>,
+}
+
+const BASELINE_VERSION: usize = 1;
+
+// Failures recorded by `--write-baseline`, each of which may suppress one
+// matching failure when used with `--baseline`.
+#[derive(Default)]
+pub struct Baseline {
+    remaining: BTreeSet<Fingerprint>,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Baseline, Error> {
+        let mut f = File::open(path)
+            .with_context(|| format!("failed to open baseline '{}'", path.to_string_lossy()))?;
+        let mut s = String::new();
+        f.read_to_string(&mut s)
+            .with_context(|| format!("failed to read baseline '{}'", path.to_string_lossy()))?;
+        let file: BaselineFile = serde_json::from_str(&s)
+            .with_context(|| format!("failed to parse baseline '{}'", path.to_string_lossy()))?;
+
+        Ok(Baseline {
+            remaining: file.failures.into_iter().collect(),
+        })
+    }
+
+    pub fn write(path: &Path, fingerprints: &[Fingerprint]) -> Result<(), Error> {
+        let mut failures = fingerprints.to_vec();
+        failures.sort();
+        let file = BaselineFile {
+            version: BASELINE_VERSION,
+            failures,
+        };
+
+        let mut f = File::create(path)
+            .with_context(|| format!("failed to create baseline '{}'", path.to_string_lossy()))?;
+        writeln!(f, "{}", serde_json::to_string_pretty(&file)?)
+            .with_context(|| format!("failed to write baseline '{}'", path.to_string_lossy()))?;
+        Ok(())
+    }
+
+    // Returns true if the failure is in the baseline, and should therefore be
+    // suppressed.
+    pub fn suppress(&mut self, fingerprint: &Fingerprint) -> bool {
+        self.remaining.remove(fingerprint)
+    }
+
+    // Entries in the baseline which haven't suppressed any failure, i.e.
+    // failures which have since been fixed.
+    pub fn unmatched(&self) -> impl Iterator<Item = &Fingerprint> {
+        self.remaining.iter()
+    }
+}
+
+// Calculate fingerprints for failures in the order they're reported.
+#[derive(Default)]
+pub struct Fingerprinter {
+    sources: HashMap<PathBuf, String>, // Given by `set_source()`, e.g. stdin.
+    source: Option<(PathBuf, String)>,
+    occurrences: HashMap<(String, String, String), usize>,
+}
+
+impl Fingerprinter {
+    // Use `s` as the text of `path` instead of the file on disk.
+    pub fn set_source(&mut self, path: &Path, s: &str) {
+        self.sources.insert(path.to_path_buf(), s.to_string());
+    }
+
+    pub fn fingerprint(&mut self, failed: &LintFailed) -> Result<Fingerprint, Error> {
+        let path = baseline_path(&failed.path);
+        let source = normalise_source(self.line(&failed.path, failed.beg)?);
+
+        let occurrence = self
+            .occurrences
+            .entry((path.clone(), failed.name.clone(), source.clone()))
+            .or_insert(0);
+        let ret = Fingerprint {
+            path,
+            rule: failed.name.clone(),
+            source,
+            occurrence: *occurrence,
+        };
+        *occurrence += 1;
+
+        Ok(ret)
+    }
+
+    // Text of the line containing byte `beg` in the file.
+    // Failures are grouped by file, so only the most recent file is kept.
+    fn line(&mut self, path: &Path, beg: usize) -> Result<&str, Error> {
+        let cached = matches!(&self.source, Some((x, _)) if x == path);
+        if !cached {
+            let s = match self.sources.get(path) {
+                Some(x) => x.clone(),
+                // Offsets are into the decoded text, as linted.
+                None => read_source(path)
+                    .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?,
+            };
+            self.source = Some((path.to_path_buf(), s));
+        }
+
+        let s: &str = &self.source.as_ref().unwrap().1;
+        let beg = (0..=beg.min(s.len()))
+            .rev()
+            .find(|x| s.is_char_boundary(*x))
+            .unwrap_or(0);
+        let line_beg = s[..beg].rfind('\n').map_or(0, |x| x + 1);
+        let line_end = s[beg..].find('\n').map_or(s.len(), |x| beg + x);
+        Ok(&s[line_beg..line_end])
+    }
+}
+
+pub fn normalise_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+// Path of a file as recorded in a baseline, i.e. relative to the current
+// directory where the file is inside it, so that the same baseline matches
+// in another checkout, whether files are given by absolute or relative paths.
+pub fn baseline_path(path: &Path) -> String {
+    let relative = if path.is_absolute() {
+        env::current_dir().ok().and_then(|dir| {
+            let relative = path.strip_prefix(&dir).ok().map(Path::to_path_buf);
+            relative.or_else(|| {
+                let (path, dir) = (path.canonicalize().ok()?, dir.canonicalize().ok()?);
+                path.strip_prefix(dir).ok().map(Path::to_path_buf)
+            })
+        })
+    } else {
+        Some(path.components().filter(|x| *x != Component::CurDir).collect())
+    };
+    normalise_path(relative.as_deref().unwrap_or(path))
+}
+
+// Collapse whitespace so that re-indentation doesn't change the fingerprint.
+fn normalise_source(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
@@ -0,0 +1,239 @@
+use crate::linter::LintFailed;
+use anyhow::{Context, Error};
+use regex::Regex;
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use sv_parser::Defines;
+
+const CACHE_VERSION: usize = 1;
+
+// Entries which haven't been used for this long are removed by `prune()`.
+const CACHE_LIFETIME: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// Result of linting one file, which is replayed while the file and everything
+// else which affects the result are unchanged.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CacheEntry {
+    version: usize,
+    key: String,
+
+    // Macros defined by the file are visible in following files, so they're
+    // found again by preprocessing, which is much faster than parsing.
+    pub defines_changed: bool,
+
+    pub n_suppressed: usize,
+    pub failed: Vec<LintFailed>,
+}
+
+// Results of linting, stored in `dir` with one entry per source file, which
+// is replaced whenever the file is linted again.
+pub struct Cache {
+    dir: PathBuf,
+    run_key: String,
+    re_include: Regex,
+    re_macro_include: Regex,
+}
+
+impl Cache {
+    // `inputs` identifies everything, other than each file and the files it
+    // includes, which affects the results, e.g. the configuration.
+    pub fn new(dir: &Path, inputs: &[String]) -> Cache {
+        let mut hasher = Sha256::new();
+        hasher.update(CACHE_VERSION.to_string());
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        for x in inputs {
+            hasher.update(b"\0");
+            hasher.update(x);
+        }
+
+        Cache {
+            dir: dir.to_path_buf(),
+            run_key: format!("{:x}", hasher.finalize()),
+            re_include: include_regex(),
+            re_macro_include: Regex::new(r"`include\s*`").unwrap(),
+        }
+    }
+
+    // Identity of the inputs to linting one file, including the text of every
+    // file it includes, directly or indirectly.
+    // Files including a file named by a macro can't be cached, as the included
+    // file isn't known until the file is preprocessed, so there's no key.
+    pub fn key(&self, path: &Path, text: &str, defines: &Defines, incdirs: &[PathBuf]) -> Option<String> {
+        if self.re_macro_include.is_match(text) {
+            return None;
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(&self.run_key);
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(b"\0");
+        hasher.update(text);
+
+        let defines: BTreeMap<&String, String> = defines.iter().map(|(k, v)| (k, format!("{:?}", v))).collect();
+        for (k, v) in defines {
+            hasher.update(b"\0");
+            hasher.update(k);
+            hasher.update(v);
+        }
+
+        for (include, x) in included_files(&self.re_include, text, incdirs) {
+            hasher.update(b"\0");
+            hasher.update(include.to_string_lossy().as_bytes());
+            // Missing files are noted, so that creating them is seen.
+            match x {
+                Some(x) => {
+                    if self.re_macro_include.is_match(&String::from_utf8_lossy(&x)) {
+                        return None;
+                    }
+                    hasher.update(&x)
+                }
+                None => hasher.update(b"\0missing"),
+            }
+        }
+
+        Some(format!("{:x}", hasher.finalize()))
+    }
+
+    fn entry_path(&self, path: &Path) -> PathBuf {
+        let name = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+        self.dir.join(format!("{}.json", &name[..32]))
+    }
+
+    // The stored result for a file, if any, with the same key.
+    // Entries which can't be read are treated as missing.
+    pub fn get(&self, path: &Path, key: &str) -> Option<CacheEntry> {
+        let entry_path = self.entry_path(path);
+        let s = fs::read_to_string(&entry_path).ok()?;
+        let entry: CacheEntry = serde_json::from_str(&s).ok()?;
+        if entry.version != CACHE_VERSION || entry.key != key {
+            return None;
+        }
+
+        // Keep entries which are used from being pruned.
+        if let Ok(f) = File::options().write(true).open(&entry_path) {
+            let _ = f.set_modified(SystemTime::now());
+        }
+        Some(entry)
+    }
+
+    pub fn put(
+        &self,
+        path: &Path,
+        key: &str,
+        defines_changed: bool,
+        n_suppressed: usize,
+        failed: Vec<LintFailed>,
+    ) -> Result<(), Error> {
+        let entry = CacheEntry {
+            version: CACHE_VERSION,
+            key: key.to_string(),
+            defines_changed,
+            n_suppressed,
+            failed,
+        };
+
+        if !self.dir.exists() {
+            fs::create_dir_all(&self.dir)
+                .with_context(|| format!("failed to create cache '{}'", self.dir.display()))?;
+            // The cache shouldn't be committed to version control.
+            fs::write(self.dir.join(".gitignore"), "*\n")?;
+        }
+
+        // Entries are written whole, then renamed, so that an entry is never
+        // read while incomplete.
+        let entry_path = self.entry_path(path);
+        let tmp_path = entry_path.with_extension(format!("{}.tmp", std::process::id()));
+        let mut f = File::create(&tmp_path)
+            .with_context(|| format!("failed to write cache '{}'", tmp_path.display()))?;
+        f.write_all(serde_json::to_string(&entry)?.as_bytes())?;
+        drop(f);
+        fs::rename(&tmp_path, &entry_path)
+            .with_context(|| format!("failed to write cache '{}'", entry_path.display()))?;
+        Ok(())
+    }
+
+    // Remove entries which haven't been used recently, e.g. those of files
+    // which have since been removed.
+    // Entries may be used or removed by other runs at the same time, so any
+    // entry which can't be read is skipped.
+    pub fn prune(&self) {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(x) => x,
+            Err(_) => return,
+        };
+        let now = SystemTime::now();
+        for path in entries.filter_map(|x| x.ok()).map(|x| x.path()) {
+            if path.extension().and_then(|x| x.to_str()) != Some("json") {
+                continue;
+            }
+            let modified = match fs::metadata(&path).and_then(|x| x.modified()) {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            if matches!(now.duration_since(modified), Ok(x) if x > CACHE_LIFETIME) {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
+
+pub fn include_regex() -> Regex {
+    Regex::new(r#"`include\s*(?:"([^"]*)"|<([^>]*)>)"#).unwrap()
+}
+
+// Every file included by `text`, directly or indirectly, with its contents,
+// or `None` where it doesn't exist.
+// Files named by `include directives are resolved in the same way as by the
+// preprocessor, i.e. relative to the working directory, then to each include
+// directory, but files named by macros aren't found.
+pub fn included_files(
+    re_include: &Regex,
+    text: &str,
+    incdirs: &[PathBuf],
+) -> Vec<(PathBuf, Option<Vec<u8>>)> {
+    let mut ret = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![text.to_string()];
+    while let Some(text) = pending.pop() {
+        for caps in re_include.captures_iter(&text) {
+            let path = PathBuf::from(caps.get(1).or(caps.get(2)).unwrap().as_str());
+            let path = if path.is_relative() && !path.exists() {
+                incdirs
+                    .iter()
+                    .map(|x| x.join(&path))
+                    .find(|x| x.exists())
+                    .unwrap_or(path)
+            } else {
+                path
+            };
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            let x = fs::read(&path).ok();
+            if let Some(x) = &x {
+                pending.push(String::from_utf8_lossy(x).to_string());
+            }
+            ret.push((path, x));
+        }
+    }
+    ret
+}
+
+// Identity of a file which is too large to hash on every run, i.e. svlint
+// itself and plugins, which changes whenever the file is rebuilt.
+pub fn file_identity(path: &Path) -> String {
+    match fs::metadata(path) {
+        Ok(x) => format!(
+            "{} {} {:?}",
+            path.display(),
+            x.len(),
+            x.modified().ok().and_then(|x| x.duration_since(SystemTime::UNIX_EPOCH).ok())
+        ),
+        Err(_) => path.display().to_string(),
+    }
+}
@@ -1,10 +1,25 @@
-use crate::linter::{SyntaxRule, TextRule};
+use crate::custom::{node_kinds, CustomSyntaxRule};
+use crate::linter::{did_you_mean, SyntaxRule, TextRule};
+use crate::query::Query;
 use crate::rules::*;
+use anyhow::{anyhow, Context, Error};
+use globset::Glob;
 use regex::Regex;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
 use serde_derive::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
+    // Configurations which this one is layered on top of, either names of
+    // built-in rulesets or paths relative to this configuration's file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+
     #[serde(default)]
     pub option: ConfigOption,
 
@@ -17,6 +32,66 @@ pub struct Config {
     // the configuration files, usually `.svlint.toml`.
     #[serde(default, alias = "rules")]
     pub syntaxrules: ConfigSyntaxRules,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<ConfigOverride>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub custom_syntaxrules: Vec<ConfigCustomSyntaxRule>,
+}
+
+// Changes to the configuration for files matching any of `globs` or
+// `regexes`, where each table holds only the values to be changed.
+// Tables are kept as TOML so that values which aren't given can be told apart
+// from defaults.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigOverride {
+    #[serde(default)]
+    pub globs: Vec<Glob>,
+
+    #[serde(with = "serde_regex", default)]
+    pub regexes: Vec<Regex>,
+
+    #[serde(default)]
+    pub option: toml::Table,
+
+    #[serde(default)]
+    pub textrules: toml::Table,
+
+    #[serde(default)]
+    pub syntaxrules: toml::Table,
+}
+
+// Syntaxrule declared in the configuration, which fails on each node of kind
+// `node`, e.g. `"CaseStatement"`, named as shown by `--dump-syntaxtree`.
+// Where `within` is given, only nodes inside nodes of each of those kinds
+// fail, and where `regex` is given, only nodes whose text matches.
+// Alternatively, the rule fails on each node selected by `query`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigCustomSyntaxRule {
+    pub name: String,
+
+    pub hint: String,
+
+    #[serde(default)]
+    pub reason: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub node: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<Query>,
+
+    #[serde(default, deserialize_with = "deserialize_within", skip_serializing_if = "Vec::is_empty")]
+    pub within: Vec<String>,
+
+    #[serde(with = "serde_regex", default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<Regex>,
+
+    #[serde(default = "default_as_error")]
+    pub severity: Severity,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -189,8 +264,419 @@ pub struct UnpackedArrayOption {
     pub net_declaration: bool,
 }
 
+// Severity of a rule's failures, given for each rule in the `[textrules]` and
+// `[syntaxrules]` sections.
+// For compatibility with older configurations, `true` is equivalent to
+// `"error"`, and `false` is equivalent to `"off"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+    Off,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+            Severity::Off => "off",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl serde::Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Severity::Error => serializer.serialize_bool(true),
+            Severity::Off => serializer.serialize_bool(false),
+            x => serializer.serialize_str(x.as_str()),
+        }
+    }
+}
+
+struct SeverityVisitor;
+
+impl<'de> Visitor<'de> for SeverityVisitor {
+    type Value = Severity;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a boolean, or one of \"error\", \"warning\", \"info\", \"off\"")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Severity, E> {
+        Ok(if v { Severity::Error } else { Severity::Off })
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Severity, E> {
+        match v {
+            "error" => Ok(Severity::Error),
+            "warning" => Ok(Severity::Warning),
+            "info" => Ok(Severity::Info),
+            "off" => Ok(Severity::Off),
+            _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Severity, D::Error> {
+        deserializer.deserialize_any(SeverityVisitor)
+    }
+}
+
+// Either a single node kind or an array of them.
+fn deserialize_within<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Within {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match <Within as serde::Deserialize>::deserialize(deserializer)? {
+        Within::One(x) => vec![x],
+        Within::Many(x) => x,
+    })
+}
+
 include!(concat!(env!("OUT_DIR"), "/config_rules.rs"));
 
+// Built-in rulesets, from the TOML in each `md/ruleset-*.md`.
+include!(concat!(env!("OUT_DIR"), "/rulesets.rs"));
+
+impl Config {
+    // This configuration with overrides applied in order, so where several
+    // overrides change the same value, the last one takes precedence.
+    pub fn with_overrides(&self, indices: &[usize]) -> Result<Config, toml::de::Error> {
+        let mut base = self.clone();
+        base.overrides.clear();
+        let mut value = toml::Table::try_from(&base).unwrap();
+
+        for i in indices {
+            let x = &self.overrides[*i];
+            for (key, table) in [
+                ("option", &x.option),
+                ("textrules", &x.textrules),
+                ("syntaxrules", &x.syntaxrules),
+            ] {
+                if let Some(toml::Value::Table(section)) = value.get_mut(key) {
+                    section.extend(table.clone());
+                }
+            }
+        }
+
+        toml::Value::Table(value).try_into()
+    }
+
+    // Rules declared by `[[custom_syntaxrules]]`, which follow the built-in
+    // syntaxrules.
+    pub fn gen_custom_syntaxrules(&self) -> Vec<(Box<dyn SyntaxRule>, Severity)> {
+        self.custom_syntaxrules
+            .iter()
+            .filter(|x| x.severity != Severity::Off)
+            .map(|x| {
+                let rule: Box<dyn SyntaxRule> = Box::new(CustomSyntaxRule::new(x.clone()));
+                (rule, x.severity)
+            })
+            .collect()
+    }
+
+    // Read a configuration file, layered on top of every configuration which
+    // it extends, in order.
+    pub fn load(path: &Path) -> Result<Config, Error> {
+        Config::load_with_files(path).map(|(x, _)| x)
+    }
+
+    // As `load()`, also giving every file which was read, i.e. the file itself
+    // and those it extends, directly or indirectly, so that changes to any of
+    // them can be seen.
+    pub fn load_with_files(path: &Path) -> Result<(Config, Vec<PathBuf>), Error> {
+        let mut files = vec![];
+        let table = load_table(&ConfigSource::File(path.to_path_buf()), &mut vec![], &mut files)?;
+        let ret: Config = toml::Value::Table(table)
+            .try_into()
+            .with_context(|| format!("failed to parse toml '{}'", path.to_string_lossy()))?;
+        ret.validate()
+            .with_context(|| format!("invalid configuration '{}'", path.to_string_lossy()))?;
+        Ok((ret, files))
+    }
+
+    // Check values which can't be checked by deserializing alone, i.e. that
+    // every regex option compiles, both in `[option]` and in each override,
+    // and that custom syntaxrules are named uniquely.
+    pub fn validate(&self) -> Result<(), Error> {
+        let option = toml::Table::try_from(&self.option).unwrap();
+        check_regexes(&option, "option")?;
+
+        for (i, x) in self.overrides.iter().enumerate() {
+            let prefix = format!("overrides[{}]", i);
+            check_regexes(&x.option, &format!("{}.option", prefix))?;
+            self.with_overrides(&[i])
+                .with_context(|| format!("invalid {}", prefix))?;
+        }
+
+        let re_name = Regex::new(r"^[a-z0-9_]+$").unwrap();
+        let builtin: Vec<String> = Config::gen_all_textrules()
+            .iter()
+            .map(|x| x.name())
+            .chain(Config::gen_all_syntaxrules().iter().map(|x| x.name()))
+            .collect();
+        for (i, x) in self.custom_syntaxrules.iter().enumerate() {
+            let prefix = format!("custom_syntaxrules[{}]", i);
+            if !re_name.is_match(&x.name) {
+                return Err(anyhow!(
+                    "invalid {}: name \"{}\" must contain only lowercase letters, digits, and underscores",
+                    prefix,
+                    x.name
+                ));
+            }
+            if builtin.contains(&x.name) {
+                return Err(anyhow!("invalid {}: name \"{}\" is a built-in rule", prefix, x.name));
+            }
+            if self.custom_syntaxrules[..i].iter().any(|y| y.name == x.name) {
+                return Err(anyhow!("invalid {}: name \"{}\" is already used", prefix, x.name));
+            }
+            match (x.node.is_empty(), &x.query) {
+                (true, None) => {
+                    return Err(anyhow!("invalid {}: either `node` or `query` is required", prefix));
+                }
+                (false, Some(_)) => {
+                    return Err(anyhow!("invalid {}: `node` and `query` can't both be given", prefix));
+                }
+                (true, Some(_)) if !x.within.is_empty() || x.regex.is_some() => {
+                    return Err(anyhow!(
+                        "invalid {}: `within` and `regex` can only be given with `node`",
+                        prefix
+                    ));
+                }
+                (true, Some(_)) => continue,
+                (false, None) => {}
+            }
+            for kind in std::iter::once(&x.node).chain(&x.within) {
+                if node_kinds().any(|x| x == kind) {
+                    continue;
+                }
+                return match did_you_mean(kind, node_kinds()) {
+                    Some(x) => Err(anyhow!(
+                        "invalid {}: \"{}\" isn't a node kind, did you mean \"{}\"?",
+                        prefix,
+                        kind,
+                        x
+                    )),
+                    None => Err(anyhow!(
+                        "invalid {}: \"{}\" isn't a node kind, e.g. \"AlwaysConstruct\"",
+                        prefix,
+                        kind
+                    )),
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Every valid key, in tables of the same shape as a configuration file, where
+// the values are only used to find nested tables.
+fn reference_table() -> toml::Table {
+    let mut ret = toml::Table::try_from(Config::new()).unwrap();
+    ret.insert(String::from("extends"), toml::Value::Array(vec![]));
+
+    if let Some(toml::Value::Table(x)) = ret.get_mut("syntaxrules") {
+        for name in RENAMED_SYNTAXRULES {
+            x.insert(name.to_string(), toml::Value::Boolean(false));
+        }
+    }
+    let syntaxrules = ret["syntaxrules"].clone();
+    ret.insert(String::from("rules"), syntaxrules);
+
+    let mut overrides = toml::Table::new();
+    overrides.insert(String::from("globs"), toml::Value::Array(vec![]));
+    overrides.insert(String::from("regexes"), toml::Value::Array(vec![]));
+    for key in ["option", "textrules", "syntaxrules"] {
+        overrides.insert(key.to_string(), ret[key].clone());
+    }
+    ret.insert(String::from("overrides"), toml::Value::Array(vec![toml::Value::Table(overrides)]));
+
+    let mut custom = toml::Table::new();
+    for key in ["name", "hint", "reason", "node", "within", "regex", "query", "severity"] {
+        custom.insert(key.to_string(), toml::Value::String(String::new()));
+    }
+    ret.insert(String::from("custom_syntaxrules"), toml::Value::Array(vec![toml::Value::Table(custom)]));
+
+    ret
+}
+
+// Report the first key in `table` which isn't in `reference`, with the most
+// similar valid key, if any.
+// Keys are named in the same dotted form as they're written in TOML, e.g.
+// `option.prefix_input` or `overrides[0].syntaxrules.case_default`.
+fn check_keys(table: &toml::Table, reference: &toml::Table, prefix: &str) -> Result<(), Error> {
+    for (key, value) in table {
+        let name = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+
+        match (reference.get(key), value) {
+            (None, _) => {
+                let msg = match did_you_mean(key, reference.keys().map(|x| x.as_str())) {
+                    Some(x) => format!("unknown key `{}`, did you mean `{}`?", name, x),
+                    None => format!("unknown key `{}`", name),
+                };
+                return Err(anyhow!(msg));
+            }
+            (Some(toml::Value::Table(r)), toml::Value::Table(t)) => check_keys(t, r, &name)?,
+            (Some(toml::Value::Array(r)), toml::Value::Array(ts)) if is_array_of_tables(key) => {
+                let r = r[0].as_table().unwrap();
+                for (i, t) in ts.iter().enumerate() {
+                    if let toml::Value::Table(t) = t {
+                        check_keys(t, r, &format!("{}[{}]", name, i))?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Arrays of tables, i.e. `[[overrides]]` and `[[custom_syntaxrules]]`, whose
+// tables are checked individually, and which are concatenated when merged.
+fn is_array_of_tables(key: &str) -> bool {
+    key == "overrides" || key == "custom_syntaxrules"
+}
+
+// Regex options are compiled by rules only when first used, so they're all
+// compiled here to report mistakes before any file is processed.
+fn check_regexes(option: &toml::Table, prefix: &str) -> Result<(), Error> {
+    for (key, value) in option {
+        if let (true, toml::Value::String(x)) = (key.starts_with("re_"), value) {
+            if let Err(e) = Regex::new(x) {
+                return Err(anyhow!("invalid regex in `{}.{}`: {}", prefix, key, e));
+            }
+        }
+    }
+    Ok(())
+}
+
+enum ConfigSource {
+    Builtin(&'static str, &'static str),
+    File(PathBuf),
+}
+
+impl ConfigSource {
+    // Entries of `extends` which look like paths are files, and anything else
+    // must be the name of a built-in ruleset.
+    fn from_extends(name: &str, dir: Option<&Path>) -> Result<ConfigSource, Error> {
+        if name.contains('/') || name.contains('\\') || name.ends_with(".toml") {
+            let path = Path::new(name);
+            let path = match dir {
+                Some(dir) if path.is_relative() => dir.join(path),
+                _ => path.to_path_buf(),
+            };
+            return Ok(ConfigSource::File(path));
+        }
+
+        if let Some((name, toml)) = RULESETS.iter().find(|(x, _)| *x == name) {
+            return Ok(ConfigSource::Builtin(name, toml));
+        }
+        let names: Vec<&str> = RULESETS.iter().map(|(x, _)| *x).collect();
+        match did_you_mean(name, names.iter().copied()) {
+            Some(x) => Err(anyhow!("unknown ruleset \"{}\" in extends, did you mean \"{}\"?", name, x)),
+            None => Err(anyhow!(
+                "unknown ruleset \"{}\" in extends, expected a path or one of: {}",
+                name,
+                names.join(", ")
+            )),
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            ConfigSource::Builtin(name, _) => format!("ruleset \"{}\"", name),
+            ConfigSource::File(path) => format!("'{}'", path.to_string_lossy()),
+        }
+    }
+}
+
+// Read a configuration as TOML, merged on top of the configurations which it
+// extends.
+// `stack` holds the configurations currently being read, which are compared
+// by canonical path, so that cycles are found however each file is named.
+// Every file read is added to `files`.
+fn load_table(
+    source: &ConfigSource,
+    stack: &mut Vec<String>,
+    files: &mut Vec<PathBuf>,
+) -> Result<toml::Table, Error> {
+    let (id, s, dir) = match source {
+        ConfigSource::Builtin(name, toml) => (format!("ruleset:{}", name), toml.to_string(), None),
+        ConfigSource::File(path) => {
+            files.push(path.clone());
+            let mut f = File::open(path)
+                .with_context(|| format!("failed to open '{}'", path.to_string_lossy()))?;
+            let mut s = String::new();
+            f.read_to_string(&mut s)
+                .with_context(|| format!("failed to read '{}'", path.to_string_lossy()))?;
+            let id = path.canonicalize().unwrap_or(path.clone());
+            (id.to_string_lossy().to_string(), s, path.parent().map(|x| x.to_path_buf()))
+        }
+    };
+
+    if stack.contains(&id) {
+        stack.push(id);
+        return Err(anyhow!("cyclic extends: {}", stack.join(" -> ")));
+    }
+
+    let mut table: toml::Table = toml::from_str(&s)
+        .with_context(|| format!("failed to parse toml {}", source.display()))?;
+    check_keys(&table, &reference_table(), "")
+        .with_context(|| format!("invalid configuration {}", source.display()))?;
+    let extends: Vec<String> = match table.remove("extends") {
+        Some(x) => x
+            .try_into()
+            .with_context(|| format!("extends must be an array of strings in {}", source.display()))?,
+        None => vec![],
+    };
+
+    stack.push(id);
+    let mut ret = toml::Table::new();
+    for name in &extends {
+        let base = ConfigSource::from_extends(name, dir.as_deref())?;
+        merge_table(&mut ret, load_table(&base, stack, files)?);
+    }
+    stack.pop();
+
+    merge_table(&mut ret, table);
+    Ok(ret)
+}
+
+// Values in `top` replace those in `base`, except that tables are merged key by
+// key, and `overrides` and `custom_syntaxrules` from both are kept, with those
+// from `top` last.
+fn merge_table(base: &mut toml::Table, top: toml::Table) {
+    for (key, value) in top {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(x)), toml::Value::Table(y)) => merge_table(x, y),
+            (Some(toml::Value::Array(x)), toml::Value::Array(y)) if is_array_of_tables(&key) => x.extend(y),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
 impl Default for ConfigOption {
     fn default() -> Self {
         toml::from_str("").unwrap()
@@ -237,6 +723,14 @@ fn default_as_false() -> bool {
     false
 }
 
+fn default_as_error() -> Severity {
+    Severity::Error
+}
+
+fn default_as_off() -> Severity {
+    Severity::Off
+}
+
 fn default_textwidth() -> usize {
     80
 }
@@ -0,0 +1,142 @@
+use crate::config::{ConfigCustomSyntaxRule, ConfigOption};
+use crate::linter::{SyntaxRule, SyntaxRuleResult};
+use std::collections::HashSet;
+use sv_parser::{unwrap_locate, NodeEvent, SyntaxTree};
+
+// Every kind of node in the syntax tree, i.e. each variant of
+// `sv_parser::RefNode`, as named by `--dump-syntaxtree`.
+// The list is taken from the sources of sv-parser-syntaxtree, as its build
+// script does, so it must be updated with sv-parser.
+pub fn node_kinds() -> impl Iterator<Item = &'static str> {
+    include_str!("node_kinds.txt").lines()
+}
+
+// Syntaxrule declared by a `[[custom_syntaxrules]]` entry in the
+// configuration, which is checked and controlled in the same way as a
+// built-in syntaxrule.
+pub struct CustomSyntaxRule {
+    config: ConfigCustomSyntaxRule,
+    within_depth: Vec<usize>, // Number of enclosing nodes of each `within` kind.
+
+    // Rules given by a query are matched against the whole syntax tree when
+    // its root is entered, so nodes are identified by the order they're
+    // entered, counting only those with a location, as given to rules.
+    depth: usize,
+    index: usize,
+    matched: HashSet<usize>,
+}
+
+impl CustomSyntaxRule {
+    pub fn new(config: ConfigCustomSyntaxRule) -> CustomSyntaxRule {
+        let within_depth = vec![0; config.within.len()];
+        CustomSyntaxRule {
+            config,
+            within_depth,
+            depth: 0,
+            index: 0,
+            matched: HashSet::new(),
+        }
+    }
+
+    fn check_query(&mut self, syntax_tree: &SyntaxTree, event: &NodeEvent) -> SyntaxRuleResult {
+        if let NodeEvent::Leave(_) = event {
+            self.depth = self.depth.saturating_sub(1);
+            return SyntaxRuleResult::Pass;
+        }
+
+        if self.depth == 0 {
+            let matched: HashSet<usize> = match &self.config.query {
+                Some(query) => query.matches(syntax_tree).into_iter().map(|x| x.index).collect(),
+                None => HashSet::new(),
+            };
+            self.matched.clear();
+            let mut index = 0;
+            for (i, node) in syntax_tree
+                .into_iter()
+                .event()
+                .filter_map(|x| match x {
+                    NodeEvent::Enter(x) => Some(x),
+                    NodeEvent::Leave(_) => None,
+                })
+                .enumerate()
+            {
+                if unwrap_locate!(node).is_some() {
+                    if matched.contains(&i) {
+                        self.matched.insert(index);
+                    }
+                    index += 1;
+                }
+            }
+            self.index = 0;
+        }
+        self.depth += 1;
+
+        let ret = if self.matched.contains(&self.index) {
+            SyntaxRuleResult::Fail
+        } else {
+            SyntaxRuleResult::Pass
+        };
+        self.index += 1;
+        ret
+    }
+}
+
+impl SyntaxRule for CustomSyntaxRule {
+    fn check(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        event: &NodeEvent,
+        _option: &ConfigOption,
+    ) -> SyntaxRuleResult {
+        if self.config.query.is_some() {
+            return self.check_query(syntax_tree, event);
+        }
+
+        let (node, enter) = match event {
+            NodeEvent::Enter(x) => (x, true),
+            NodeEvent::Leave(x) => (x, false),
+        };
+        let kind = node.to_string();
+
+        if !enter {
+            for (depth, within) in self.within_depth.iter_mut().zip(&self.config.within) {
+                if *within == kind {
+                    *depth = depth.saturating_sub(1);
+                }
+            }
+            return SyntaxRuleResult::Pass;
+        }
+
+        // A node isn't within itself, so it's matched before being counted.
+        let matched = kind == self.config.node
+            && self.within_depth.iter().all(|x| *x > 0)
+            && match &self.config.regex {
+                Some(re) => re.is_match(syntax_tree.get_str_trim(vec![node.clone()]).unwrap_or("")),
+                None => true,
+            };
+
+        for (depth, within) in self.within_depth.iter_mut().zip(&self.config.within) {
+            if *within == kind {
+                *depth += 1;
+            }
+        }
+
+        if matched {
+            SyntaxRuleResult::Fail
+        } else {
+            SyntaxRuleResult::Pass
+        }
+    }
+
+    fn name(&self) -> String {
+        self.config.name.clone()
+    }
+
+    fn hint(&self, _option: &ConfigOption) -> String {
+        self.config.hint.clone()
+    }
+
+    fn reason(&self) -> String {
+        self.config.reason.clone()
+    }
+}
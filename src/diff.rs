@@ -0,0 +1,169 @@
+use crate::linter::{read_source, LintFailed};
+use anyhow::{anyhow, Context, Error};
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+// Lines added or modified by a unified diff, so that only failures on those
+// lines are reported.
+// Files are identified by their paths in the diff, relative to the current
+// directory, which are compared with the paths of failures however those are
+// given, e.g. absolute or through `../`.
+#[derive(Debug, Default)]
+pub struct Diff {
+    lines: HashMap<PathBuf, BTreeSet<usize>>, // 1-based line numbers in the new file.
+    source: Option<(PathBuf, Vec<usize>)>,   // Byte offset of each line of the most recent file.
+    sources: HashMap<PathBuf, String>,       // Given by `set_source()`, e.g. stdin.
+}
+
+impl Diff {
+    pub fn load(path: &Path) -> Result<Diff, Error> {
+        let mut f = File::open(path)
+            .with_context(|| format!("failed to open diff '{}'", path.to_string_lossy()))?;
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)
+            .with_context(|| format!("failed to read diff '{}'", path.to_string_lossy()))?;
+        Diff::parse(&String::from_utf8_lossy(&buf))
+            .with_context(|| format!("failed to parse diff '{}'", path.to_string_lossy()))
+    }
+
+    // Changes in the working tree since `base`, e.g. a branch or commit, with
+    // paths relative to the working directory, like those given to svlint.
+    pub fn from_git(base: &str) -> Result<Diff, Error> {
+        let output = Command::new("git")
+            .args(["diff", "--relative", "--no-color", "--no-ext-diff", "--src-prefix=a/", "--dst-prefix=b/", "-U0"])
+            .arg(base)
+            .arg("--")
+            .output()
+            .context("failed to run git")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "failed to get changes since '{}': {}",
+                base,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Diff::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    pub fn parse(s: &str) -> Result<Diff, Error> {
+        let mut ret = Diff::default();
+        let mut path: Option<PathBuf> = None;
+        let mut line = 0; // Number of the next line in the new file.
+        let mut remaining = (0, 0); // Lines of the current hunk in the old and new files.
+
+        for (i, x) in s.lines().enumerate() {
+            if remaining != (0, 0) {
+                match x.chars().next() {
+                    Some('+') => {
+                        if let Some(path) = &path {
+                            ret.lines.entry(path.clone()).or_default().insert(line);
+                        }
+                        line += 1;
+                        remaining.1 -= 1;
+                    }
+                    Some('-') => remaining.0 -= 1,
+                    Some('\\') => {}
+                    _ => {
+                        line += 1;
+                        remaining = (remaining.0 - 1, remaining.1 - 1);
+                    }
+                }
+                if remaining.0 < 0 || remaining.1 < 0 {
+                    return Err(anyhow!("line {}: hunk is longer than its header", i + 1));
+                }
+            } else if let Some(x) = x.strip_prefix("+++ ") {
+                // Removed files have no lines to report.
+                let x = x.split('\t').next().unwrap_or("").trim_end();
+                path = match x {
+                    "/dev/null" => None,
+                    x => Some(resolve(Path::new(x.strip_prefix("b/").unwrap_or(x)))),
+                };
+            } else if let Some(x) = x.strip_prefix("@@ ") {
+                let (old, new) = parse_hunk_header(x)
+                    .ok_or_else(|| anyhow!("line {}: invalid hunk header '{}'", i + 1, x))?;
+                line = new.0;
+                remaining = (old.1 as isize, new.1 as isize);
+            }
+        }
+        if remaining != (0, 0) {
+            return Err(anyhow!("hunk is shorter than its header"));
+        }
+
+        Ok(ret)
+    }
+
+    // Use `s` as the text of `path` instead of the file on disk.
+    pub fn set_source(&mut self, path: &Path, s: &str) {
+        self.sources.insert(path.to_path_buf(), s.to_string());
+    }
+
+    // Returns true if any line spanned by the failure was added or modified.
+    pub fn contains(&mut self, failed: &LintFailed) -> Result<bool, Error> {
+        let lines = match self.lines.get(&resolve(&failed.path)) {
+            Some(x) => x,
+            None => return Ok(false),
+        };
+
+        // Failures are grouped by file, so only the most recent file is kept.
+        let cached = matches!(&self.source, Some((x, _)) if x == &failed.path);
+        if !cached {
+            // Offsets are into the decoded text, as linted.
+            let s = match self.sources.get(&failed.path) {
+                Some(x) => x.clone(),
+                None => read_source(&failed.path)
+                    .with_context(|| format!("failed to open: '{}'", failed.path.to_string_lossy()))?,
+            };
+            let line_begs = std::iter::once(0)
+                .chain(s.match_indices('\n').map(|(i, _)| i + 1))
+                .collect();
+            self.source = Some((failed.path.clone(), line_begs));
+        }
+
+        let line_begs = &self.source.as_ref().unwrap().1;
+        let line = |x: usize| line_begs.partition_point(|beg| *beg <= x);
+        let end = failed.beg + failed.len.max(1) - 1;
+        Ok(lines.range(line(failed.beg)..=line(end)).next().is_some())
+    }
+}
+
+// Absolute path of a file, with symbolic links resolved where the file exists,
+// so that every path to the same file is equal.
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(x) = path.canonicalize() {
+        return x;
+    }
+    let path = match env::current_dir() {
+        Ok(dir) => dir.join(path),
+        Err(_) => path.to_path_buf(),
+    };
+    let mut ret = PathBuf::new();
+    for x in path.components() {
+        match x {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                ret.pop();
+            }
+            x => ret.push(x),
+        }
+    }
+    ret
+}
+
+// Start and length of the old and new ranges of a hunk, from a header like
+// `@@ -1,2 +3,4 @@`, where an omitted length is 1.
+fn parse_hunk_header(s: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut ranges = s.split_whitespace();
+    let range = |x: Option<&str>, prefix: char| -> Option<(usize, usize)> {
+        let mut x = x?.strip_prefix(prefix)?.splitn(2, ',');
+        let beg = x.next()?.parse().ok()?;
+        let len = x.next().map_or(Some(1), |x| x.parse().ok())?;
+        Some((beg, len))
+    };
+    let old = range(ranges.next(), '-')?;
+    let new = range(ranges.next(), '+')?;
+    Some((old, new))
+}
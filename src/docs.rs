@@ -0,0 +1,67 @@
+use crate::config::{Config, RULESETS};
+use regex::Regex;
+use std::collections::HashMap;
+
+// Documentation of a built-in rule, from the same sources as MANUAL.md, i.e.
+// `md/*rules-explanation-*.md` and `testcases/*rules/{pass,fail}/*.sv`.
+pub struct RuleDoc {
+    pub name: &'static str,
+    pub ruletype: &'static str, // Either "text" or "syntax".
+    pub explanation: &'static str,
+    pub pass: &'static str,
+    pub fail: &'static str,
+    pub options: &'static [&'static str], // Used from `ConfigOption`.
+}
+
+include!(concat!(env!("OUT_DIR"), "/rule_docs.rs"));
+
+pub fn rule_doc(name: &str) -> Option<&'static RuleDoc> {
+    RULE_DOCS.iter().find(|x| x.name == name)
+}
+
+// Split a file of testcases, which are separated by a line of 80 slashes.
+pub fn testcases(text: &str) -> Vec<String> {
+    let sep = "/".repeat(80);
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .split(|l| l.contains(sep.as_str()))
+        .map(|x| x.join("\n"))
+        .collect()
+}
+
+// Names of the built-in rulesets which enable each rule.
+pub fn ruleset_membership() -> HashMap<String, Vec<&'static str>> {
+    let mut ret: HashMap<String, Vec<&'static str>> = HashMap::new();
+    for (ruleset, toml) in RULESETS {
+        let config: Config = toml::from_str(toml).unwrap();
+        let textrules = config.gen_textrules().into_iter().map(|x| x.name());
+        let syntaxrules = config.gen_syntaxrules().into_iter().map(|x| x.name());
+        for name in textrules.chain(syntaxrules) {
+            ret.entry(name).or_default().push(*ruleset);
+        }
+    }
+    ret
+}
+
+pub fn rulesets_enabling(name: &str) -> Vec<&'static str> {
+    ruleset_membership().remove(name).unwrap_or_default()
+}
+
+// Category of a rule, as used to arrange the rules in MANUAL.md, i.e. either
+// "functional", "naming", or "style".
+pub fn rule_category(name: &str) -> &'static str {
+    let style_prefixes = ["style_", "tab_"].join("|");
+    let re_style: Regex = Regex::new(format!("^({})", style_prefixes).as_str()).unwrap();
+
+    let naming_prefixes = ["prefix_", "lowercamelcase_", "uppercamelcase_", "re_"].join("|");
+    let re_naming: Regex =
+        Regex::new(format!("(^({})|_with_label$)", naming_prefixes).as_str()).unwrap();
+
+    if re_style.is_match(name) {
+        "style"
+    } else if re_naming.is_match(name) {
+        "naming"
+    } else {
+        "functional"
+    }
+}
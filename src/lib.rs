@@ -1,4 +1,13 @@
+pub mod baseline;
+pub mod cache;
 pub mod config;
+pub mod custom;
+pub mod diff;
+pub mod docs;
 pub mod linter;
+pub mod lsp;
+pub mod plugin;
 pub mod printer;
+pub mod query;
 pub mod rules;
+pub mod watch;
@@ -1,50 +1,52 @@
-use crate::config::{Config, ConfigOption};
+use crate::config::{Config, ConfigOption, Severity};
+use crate::plugin::{PluginInfo, PluginRule, PLUGIN_ABI_VERSION};
+use anyhow::{anyhow, Context, Error};
+use chardetng::EncodingDetector;
+use globset::GlobMatcher;
 use libloading::{Library, Symbol};
 use regex::Regex;
-use std::collections::HashMap;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use sv_parser::{unwrap_locate, Locate, NodeEvent, RefNode, SyntaxTree};
+use sv_parser::{unwrap_locate, Keyword, Locate, NodeEvent, RefNode, SyntaxTree};
 
-// Rule enum is for use by plugins.
+// Rule enum is for use by plugins, via `svlint_plugin!`.
 #[derive(Clone, Copy)]
 pub enum Rule {
     Text(*mut dyn TextRule),
     Syntax(*mut dyn SyntaxRule),
 }
 
-// Macro for use within plugins.
-// Example usage in a plugin's `get_plugin` function:
-//    pluginrules!(
-//        SamplePlugin,
-//        AnotherPlugin,
-//    )
-#[macro_export]
-macro_rules! pluginrules {
-    ( $( $x:ty ),* $(,)? ) => {
-        {
-            let mut vec: Vec<Rule> = Vec::new();
-            $(
-                let rule = <$x>::default();
-                vec.push(rule.into_rule());
-            )*
-            vec
-        }
-    };
-}
-
 #[derive(Clone, Copy)]
 pub enum TextRuleEvent<'a> {
     StartOfFile,
     Line(&'a str),
 }
 
-#[derive(Clone, Copy)]
+// Replacement of `len` bytes, beginning at `beg`, with `text`.
+// Rules attach fixes to failures which have a mechanical solution, i.e. where
+// there is only one sensible way of fixing the failure.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Fix {
+    pub beg: usize,
+    pub len: usize,
+    pub text: String,
+}
+
+#[derive(Clone)]
 pub enum TextRuleResult {
     Pass,
     Fail {
         offset: usize, // Character index, on this line, beginning failure.
         len: usize, // Number of characters causing failure.
     },
+    FailFix {
+        offset: usize,
+        len: usize,
+        fixes: Vec<Fix>, // Fixes with `beg` relative to the start of this line.
+    },
 }
 
 pub trait TextRule: Sync + Send {
@@ -66,12 +68,15 @@ pub trait TextRule: Sync + Send {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub enum SyntaxRuleResult {
     Pass,
     Fail,
     FailAt(usize, usize),
     FailLocate(Locate),
+    // Fail on this node, where each span of the syntax tree's text may be
+    // replaced to fix the failure.
+    FailFix(Vec<(Locate, String)>),
 }
 
 pub trait SyntaxRule: Sync + Send {
@@ -95,15 +100,80 @@ pub trait SyntaxRule: Sync + Send {
 }
 
 pub struct Linter {
+    config: Config,
+    overrides: Vec<OverrideMatcher>,
+    overridden: Vec<usize>,
     option: ConfigOption,
     textrules: Vec<Box<dyn TextRule>>,
     syntaxrules: Vec<Box<dyn SyntaxRule>>,
+    n_textrules: usize,
+    n_syntaxrules: usize,
+    custom_syntaxrules: HashSet<String>,
     plugins: Vec<Library>,
+    plugin_sources: HashMap<String, PathBuf>,
+    severities: HashMap<String, Severity>,
     re_ctl: Regex,
     pub ctl_enabled: HashMap<String, bool>,
+    ctl_known: HashSet<String>,
+    ctl_suppressions: Vec<CtlSuppression>,
+    ctl_unknown: Vec<LintFailed>,
+    ctl_off: HashMap<String, usize>,
+    ctl_ignored_lines: HashMap<u32, Vec<(String, usize)>>,
+    ctl_enabled_textrules: HashMap<String, Option<usize>>,
+    ctl_next_line_textrules: Vec<(String, usize)>,
+    ctl_n_suppressed: usize,
+    ctl_report_unused: bool,
+}
+
+// Paths to which one of `[[overrides]]` applies, compiled once for all files.
+// Overrides which can't be applied, as reported by `Config::validate()`, match
+// no paths, so that applying any set of matching overrides can't fail.
+struct OverrideMatcher {
+    globs: Vec<GlobMatcher>,
+    regexes: Vec<Regex>,
+}
+
+impl OverrideMatcher {
+    // Paths are matched as given, except that any leading `./` is ignored by
+    // globs, e.g. `tb/**` matches both `tb/foo.sv` and `./tb/foo.sv`.
+    fn is_match(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(".").unwrap_or(path);
+        self.globs.iter().any(|x| x.is_match(relative))
+            || self.regexes.iter().any(|x| x.is_match(&path.to_string_lossy()))
+    }
+}
+
+// Action of a control comment, e.g. `/* svlint off foo */` or
+// `// svlint ignore foo, bar`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CtlAction {
+    On,          // Enable rules from here onwards.
+    Off,         // Disable rules from here onwards.
+    OffNextLine, // Disable rules on the following line only.
+    Ignore,      // Disable rules on this line only.
+}
+
+// One control comment, where `names` holds each name as written, together
+// with the enabled rules it refers to.
+struct CtlDirective {
+    action: CtlAction,
+    beg: usize, // Byte index, within the text, beginning the comment.
+    len: usize,
+    names: Vec<(String, Vec<String>)>,
+}
+
+// One name in an `off`, `off-next-line`, or `ignore` control comment, which
+// is used once it suppresses any failure.
+// Comments which don't originate from a source file, e.g. from a macro, are
+// tracked without a location.
+struct CtlSuppression {
+    origin: Option<(PathBuf, usize)>,
+    len: usize,
+    name: String,
+    used: bool,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct LintFailed {
     pub path: PathBuf,
     pub beg: usize,
@@ -111,108 +181,418 @@ pub struct LintFailed {
     pub name: String,
     pub hint: String,
     pub reason: String,
+    pub severity: Severity,
+    pub fixes: Vec<Fix>,
 }
 
 impl Linter {
     pub fn new(config: Config) -> Linter {
-        let textrules = config.gen_textrules();
-        let syntaxrules = config.gen_syntaxrules();
-
-        // NOTE: Only syntaxrules are comment-controllable, not textrules.
-        let re_ctl = Regex::new(r"/\*\s*svlint\s+(on|off)\s+([a-z0-9_]+)\s*\*/").unwrap();
-        let mut ctl_enabled = HashMap::new();
-        for rule in &syntaxrules {
-            ctl_enabled.insert(rule.name(), true);
+        // Either `/* svlint off foo */` or `// svlint off foo`, where the
+        // action may be followed by a comma-separated list of rules.
+        // Syntaxrules are controlled by the position of comments in the
+        // syntax tree, but textrules are controlled line by line.
+        let re_ctl = Regex::new(
+            r"(?:/\*|//)\s*svlint\s+(on|off-next-line|off|ignore)\s+([a-z0-9_]+(?:\s*,\s*[a-z0-9_]+)*)\s*(?:\*/|$)",
+        )
+        .unwrap();
+
+        // Control comments may name rules which aren't enabled by this
+        // configuration, but not rules which don't exist.
+        let mut ctl_known = HashSet::new();
+        for rule in Config::gen_all_textrules() {
+            ctl_known.insert(rule.name());
+        }
+        for rule in Config::gen_all_syntaxrules() {
+            ctl_known.insert(rule.name());
+        }
+        for rule in &config.custom_syntaxrules {
+            ctl_known.insert(rule.name.clone());
         }
 
-        Linter {
-            option: config.option,
-            textrules,
-            syntaxrules,
+        let overrides = config
+            .overrides
+            .iter()
+            .enumerate()
+            .map(|(i, x)| match config.with_overrides(&[i]) {
+                Ok(_) => OverrideMatcher {
+                    globs: x.globs.iter().map(|x| x.compile_matcher()).collect(),
+                    regexes: x.regexes.clone(),
+                },
+                Err(_) => OverrideMatcher {
+                    globs: vec![],
+                    regexes: vec![],
+                },
+            })
+            .collect();
+
+        let mut ret = Linter {
+            option: config.option.clone(),
+            config,
+            overrides,
+            overridden: Vec::new(),
+            textrules: Vec::new(),
+            syntaxrules: Vec::new(),
+            n_textrules: 0,
+            n_syntaxrules: 0,
+            custom_syntaxrules: HashSet::new(),
             plugins: Vec::new(),
+            plugin_sources: HashMap::new(),
+            severities: HashMap::new(),
             re_ctl,
-            ctl_enabled,
+            ctl_enabled: HashMap::new(),
+            ctl_known,
+            ctl_suppressions: Vec::new(),
+            ctl_unknown: Vec::new(),
+            ctl_off: HashMap::new(),
+            ctl_ignored_lines: HashMap::new(),
+            ctl_enabled_textrules: HashMap::new(),
+            ctl_next_line_textrules: Vec::new(),
+            ctl_n_suppressed: 0,
+            ctl_report_unused: false,
+        };
+        let config = ret.config.clone();
+        ret.configure(&config);
+        ret
+    }
+
+    // Replace the built-in and custom rules and options, keeping any rules
+    // from plugins, which follow them.
+    fn configure(&mut self, config: &Config) {
+        self.option = config.option.clone();
+        self.severities.clear();
+
+        let mut textrules = Vec::new();
+        for (rule, severity) in config.gen_textrules_with_severity() {
+            self.severities.insert(rule.name(), severity);
+            textrules.push(rule);
+        }
+        let n_textrules = textrules.len();
+        self.textrules.splice(..self.n_textrules, textrules);
+        self.n_textrules = n_textrules;
+
+        let mut syntaxrules = Vec::new();
+        for (rule, severity) in config.gen_syntaxrules_with_severity().into_iter().chain(config.gen_custom_syntaxrules()) {
+            self.severities.insert(rule.name(), severity);
+            syntaxrules.push(rule);
+        }
+        let n_syntaxrules = syntaxrules.len();
+        self.syntaxrules.splice(..self.n_syntaxrules, syntaxrules);
+        self.n_syntaxrules = n_syntaxrules;
+        self.custom_syntaxrules = config.custom_syntaxrules.iter().map(|x| x.name.clone()).collect();
+
+        self.ctl_enabled_textrules.clear();
+        for rule in &self.textrules {
+            self.severities.entry(rule.name()).or_insert(Severity::Error);
+            self.ctl_enabled_textrules.insert(rule.name(), None);
+        }
+        self.ctl_enabled.clear();
+        for rule in &self.syntaxrules {
+            self.severities.entry(rule.name()).or_insert(Severity::Error);
+            self.ctl_enabled.insert(rule.name(), true);
         }
     }
 
-    pub fn load(&mut self, path: &Path) -> Result<(), libloading::Error> {
-        let lib = unsafe { Library::new(path) }?;
+    // Resolve the configuration for a file from the overrides which match its
+    // path, only replacing rules when the matching overrides change.
+    fn apply_overrides(&mut self, path: &Path) {
+        if self.overrides.is_empty() {
+            return;
+        }
 
-        self.plugins.push(lib);
-        let lib = self.plugins.last().unwrap();
-
-        let get_plugin: Result<Symbol<extern "C" fn() -> Vec<Rule>>, _> =
-            unsafe { lib.get(b"get_plugin") };
-        if let Ok(get_plugin) = get_plugin {
-            let plugins = get_plugin();
-            for plugin in plugins {
-                match plugin {
-                    Rule::Text(p) => {
-                        let plugin = unsafe { Box::from_raw(p) };
-                        self.textrules.push(plugin);
-                    },
-                    Rule::Syntax(p) => {
-                        let plugin = unsafe { Box::from_raw(p) };
-                        self.ctl_enabled.insert(plugin.name(), true);
-                        self.syntaxrules.push(plugin);
-                    },
+        let matched: Vec<usize> = self
+            .overrides
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.is_match(path))
+            .map(|(i, _)| i)
+            .collect();
+        if matched != self.overridden {
+            // Each matching override can be applied alone, so they can all be
+            // applied together.
+            if let Ok(config) = self.config.with_overrides(&matched) {
+                self.configure(&config);
+            }
+            self.overridden = matched;
+        }
+    }
+
+    // Load rules from a plugin, which is rejected unless it was built against
+    // the same plugin interface and the same build of svlint.
+    pub fn load(&mut self, path: &Path) -> Result<(), Error> {
+        let lib = unsafe { Library::new(path) }
+            .with_context(|| format!("failed to load plugin '{}'", path.display()))?;
+
+        let abi_version: Symbol<extern "C" fn() -> u32> =
+            match unsafe { lib.get(b"svlint_plugin_abi_version") } {
+                Ok(x) => x,
+                Err(_) if unsafe { lib.get::<*const ()>(b"get_plugin") }.is_ok() => {
+                    return Err(anyhow!(
+                        "plugin '{}' uses the unversioned plugin interface (`get_plugin`), \
+                         which is no longer supported; rebuild it with `svlint_plugin!`",
+                        path.display()
+                    ));
                 }
+                Err(_) => {
+                    return Err(anyhow!(
+                        "'{}' is not an svlint plugin, as it doesn't export `svlint_plugin_abi_version`",
+                        path.display()
+                    ));
+                }
+            };
+        let abi_version = abi_version();
+        if abi_version != PLUGIN_ABI_VERSION {
+            return Err(anyhow!(
+                "plugin '{}' implements plugin interface version {}, but svlint {} requires version {}",
+                path.display(),
+                abi_version,
+                env!("CARGO_PKG_VERSION"),
+                PLUGIN_ABI_VERSION
+            ));
+        }
+
+        let info: Symbol<extern "C" fn() -> PluginInfo> = unsafe { lib.get(b"svlint_plugin_info") }
+            .with_context(|| format!("failed to read plugin '{}'", path.display()))?;
+        let (info, current) = (info(), PluginInfo::current());
+        if info.build_id != current.build_id {
+            let versions = (info.svlint_version(), info.rustc_version());
+            if versions == (current.svlint_version(), current.rustc_version()) {
+                return Err(anyhow!(
+                    "plugin '{}' was built with an incompatible version of sv-parser",
+                    path.display()
+                ));
+            }
+            return Err(anyhow!(
+                "plugin '{}' was built with svlint {} ({}), but must be built with svlint {} ({})",
+                path.display(),
+                versions.0,
+                versions.1,
+                current.svlint_version(),
+                current.rustc_version()
+            ));
+        }
+
+        extern "C" fn push(ctx: *mut std::ffi::c_void, rule: PluginRule) {
+            let rules = unsafe { &mut *(ctx as *mut Vec<PluginRule>) };
+            rules.push(rule);
+        }
+        let plugin_rules: Symbol<
+            extern "C" fn(extern "C" fn(*mut std::ffi::c_void, PluginRule), *mut std::ffi::c_void),
+        > = unsafe { lib.get(b"svlint_plugin_rules") }
+            .with_context(|| format!("failed to read plugin '{}'", path.display()))?;
+        let mut rules: Vec<PluginRule> = Vec::new();
+        plugin_rules(push, &mut rules as *mut Vec<PluginRule> as *mut std::ffi::c_void);
+
+        // The library must outlive its rules, so it's kept until the linter is
+        // dropped, which drops the rules first.
+        self.plugins.push(lib);
+        for plugin in rules.into_iter().map(|x| x.into_rule()) {
+            match plugin {
+                Rule::Text(p) => {
+                    let plugin = unsafe { Box::from_raw(p) };
+                    self.severities.insert(plugin.name(), Severity::Error);
+                    self.ctl_known.insert(plugin.name());
+                    self.ctl_enabled_textrules.insert(plugin.name(), None);
+                    self.plugin_sources.insert(plugin.name(), path.to_path_buf());
+                    self.textrules.push(plugin);
+                },
+                Rule::Syntax(p) => {
+                    let plugin = unsafe { Box::from_raw(p) };
+                    self.severities.insert(plugin.name(), Severity::Error);
+                    self.ctl_known.insert(plugin.name());
+                    self.ctl_enabled.insert(plugin.name(), true);
+                    self.plugin_sources.insert(plugin.name(), path.to_path_buf());
+                    self.syntaxrules.push(plugin);
+                },
             }
         }
 
         Ok(())
     }
 
+    // Whether unused suppressions are to be found by `ctl_failures()`, which
+    // needs syntaxrules to be checked even where they're disabled by control
+    // comments.
+    pub fn set_report_unused_suppressions(&mut self, x: bool) {
+        self.ctl_report_unused = x;
+    }
+
+    // Number of failures suppressed by control comments since the start of
+    // the current file.
+    pub fn n_suppressed(&self) -> usize {
+        self.ctl_n_suppressed
+    }
+
+    // Name, type ("text" or "syntax"), and library path of each rule loaded
+    // from plugins, in the order they were loaded.
+    pub fn plugin_rules(&self) -> Vec<(String, &'static str, PathBuf)> {
+        let textrules = self.textrules[self.n_textrules..]
+            .iter()
+            .map(|x| (x.name(), "text"));
+        let syntaxrules = self.syntaxrules[self.n_syntaxrules..]
+            .iter()
+            .map(|x| (x.name(), "syntax"));
+        textrules
+            .chain(syntaxrules)
+            .map(|(name, ruletype)| {
+                let path = self.plugin_sources[&name].clone();
+                (name, ruletype, path)
+            })
+            .collect()
+    }
+
     pub fn textrules_check(&mut self, event: TextRuleEvent, path: &Path, beg: &usize) -> Vec<LintFailed> {
+        let ctl_disabled = match event {
+            TextRuleEvent::StartOfFile => {
+                self.apply_overrides(path);
+
+                // Comment control doesn't carry over from one file to the next,
+                // which would otherwise depend on the order files are processed.
+                for enabled in self.ctl_enabled.values_mut() {
+                    *enabled = true;
+                }
+                self.ctl_suppressions.clear();
+                self.ctl_unknown.clear();
+                self.ctl_off.clear();
+                for enabled in self.ctl_enabled_textrules.values_mut() {
+                    *enabled = None;
+                }
+                self.ctl_next_line_textrules.clear();
+                self.ctl_n_suppressed = 0;
+                vec![]
+            }
+            TextRuleEvent::Line(x) => self.update_ctl_enabled_textrules(x, path, *beg),
+        };
 
         let mut ret = Vec::new();
         'outer: for rule in &mut self.textrules {
-            match rule.check(event, &self.option) {
-                TextRuleResult::Fail {offset, len} => {
-                    match event {
-                        TextRuleEvent::StartOfFile => {}
-                        TextRuleEvent::Line(_) => {
-                            for exclude in &self.option.exclude_paths {
-                                if exclude.is_match(&path.to_string_lossy()) {
-                                    continue 'outer;
-                                }
-                            }
-                            let result = LintFailed {
-                                path: path.to_path_buf(),
-                                beg: beg + offset,
-                                len,
-                                name: rule.name(),
-                                hint: rule.hint(&self.option),
-                                reason: rule.reason(),
-                            };
-                            ret.push(result);
+            // Textrules are checked on every line, even where they're disabled,
+            // so that any state kept between lines is consistent.
+            let (offset, len, fixes) = match rule.check(event, &self.option) {
+                TextRuleResult::Fail {offset, len} => (offset, len, vec![]),
+                TextRuleResult::FailFix {offset, len, fixes} => (offset, len, fixes),
+                _ => continue 'outer,
+            };
+            match event {
+                TextRuleEvent::StartOfFile => {}
+                TextRuleEvent::Line(_) => {
+                    let mut disabled = false;
+                    for (name, i) in &ctl_disabled {
+                        if *name == rule.name() {
+                            self.ctl_suppressions[*i].used = true;
+                            disabled = true;
+                        }
+                    }
+                    if disabled {
+                        self.ctl_n_suppressed += 1;
+                        continue 'outer;
+                    }
+                    for exclude in &self.option.exclude_paths {
+                        if exclude.is_match(&path.to_string_lossy()) {
+                            continue 'outer;
                         }
                     }
+                    let fixes = fixes
+                        .into_iter()
+                        .map(|x| Fix { beg: beg + x.beg, ..x })
+                        .collect();
+                    let result = LintFailed {
+                        path: path.to_path_buf(),
+                        beg: beg + offset,
+                        len,
+                        name: rule.name(),
+                        hint: rule.hint(&self.option),
+                        reason: rule.reason(),
+                        severity: self.severities[&rule.name()],
+                        fixes,
+                    };
+                    ret.push(result);
+                }
+            }
+        }
+        ret
+    }
+
+    // Update the state of textrules from any control comments on this line,
+    // returning the names of rules which are disabled for this line, each
+    // with the suppression which disables it.
+    // A rule is disabled for the whole of any line where it's turned off, and
+    // for the whole of any line where it's turned back on.
+    fn update_ctl_enabled_textrules(&mut self, line: &str, path: &Path, beg: usize) -> Vec<(String, usize)> {
+        let mut ret: Vec<(String, usize)> = self.ctl_next_line_textrules.drain(..).collect();
+        for (name, disabled) in &self.ctl_enabled_textrules {
+            if let Some(i) = disabled {
+                ret.push((name.clone(), *i));
+            }
+        }
+
+        // Unknown names are only reported from this pass over the text, as
+        // it sees every control comment exactly once.
+        for directive in ctl_directives(&self.re_ctl, line, &self.ctl_enabled_textrules) {
+            let beg = beg + directive.beg;
+            for (written, names) in directive.names {
+                if written != "all" && !self.ctl_known.contains(&written) {
+                    let failed = self.ctl_unknown_failed(path, beg, directive.len, &written);
+                    self.ctl_unknown.push(failed);
+                    continue;
+                }
+                if names.is_empty() {
+                    continue;
+                }
+
+                if directive.action == CtlAction::On {
+                    for name in names {
+                        self.ctl_enabled_textrules.insert(name, None);
+                    }
+                    continue;
+                }
+                let i = self.ctl_suppression(Some((path.to_path_buf(), beg)), directive.len, written);
+                for name in names {
+                    match directive.action {
+                        CtlAction::Off => {
+                            self.ctl_enabled_textrules.insert(name.clone(), Some(i));
+                            ret.push((name, i));
+                        }
+                        CtlAction::OffNextLine => self.ctl_next_line_textrules.push((name, i)),
+                        _ => ret.push((name, i)),
+                    }
                 }
-                _ => (),
             }
         }
+
         ret
     }
 
     fn update_ctl_enabled(&mut self, syntax_tree: &SyntaxTree, event: &NodeEvent) {
         match event {
+            NodeEvent::Enter(RefNode::SourceText(x)) => {
+                self.update_ctl_ignored_lines(syntax_tree, syntax_tree.get_str(*x));
+            }
             NodeEvent::Enter(RefNode::Comment(x)) => {
                 let loc: Option<&Locate> = unwrap_locate!(*x);
                 let text: Option<&str> = match &loc {
                     Some(x) => syntax_tree.get_str(*x),
                     _ => None,
                 };
-                let caps = self.re_ctl.captures(text.unwrap());
-                if caps.is_some() {
-                    let caps = caps.unwrap();
-                    let ctl_name = caps.get(2).unwrap().as_str();
-                    if self.ctl_enabled.contains_key(ctl_name) {
-                        let ctl_enable = match caps.get(1).unwrap().as_str() {
-                            "off" => false,
-                            _ => true,
-                        };
-                        self.ctl_enabled.insert(ctl_name.to_string(), ctl_enable);
+                let origin = loc.and_then(|x| syntax_tree.get_origin(x));
+                for directive in ctl_directives(&self.re_ctl, text.unwrap(), &self.ctl_enabled) {
+                    let origin = origin.map(|(path, beg)| (path.clone(), beg + directive.beg));
+                    for (written, names) in directive.names {
+                        match directive.action {
+                            CtlAction::On => {
+                                for name in names {
+                                    self.ctl_off.remove(&name);
+                                    self.ctl_enabled.insert(name, true);
+                                }
+                            }
+                            CtlAction::Off if !names.is_empty() => {
+                                let i = self.ctl_suppression(origin.clone(), directive.len, written);
+                                for name in names {
+                                    self.ctl_off.insert(name.clone(), i);
+                                    self.ctl_enabled.insert(name, false);
+                                }
+                            }
+                            _ => {}
+                        }
                     }
                 }
             }
@@ -220,6 +600,111 @@ impl Linter {
         }
     }
 
+    // Line-based control comments (`ignore` and `off-next-line`) may appear
+    // after the nodes they apply to, so all comments in the syntax tree are
+    // collected before any rules are checked.
+    fn update_ctl_ignored_lines(&mut self, syntax_tree: &SyntaxTree, text: Option<&str>) {
+        self.ctl_ignored_lines.clear();
+        if !text.is_some_and(|x| x.contains("svlint")) {
+            return;
+        }
+
+        for node in syntax_tree {
+            if let RefNode::Comment(x) = node {
+                let loc: &Locate = unwrap_locate!(x).unwrap();
+                let text: &str = syntax_tree.get_str(loc).unwrap();
+                let origin = syntax_tree.get_origin(loc);
+                for directive in ctl_directives(&self.re_ctl, text, &self.ctl_enabled) {
+                    let line = match directive.action {
+                        CtlAction::Ignore => loc.line,
+                        CtlAction::OffNextLine => loc.line + 1,
+                        _ => continue,
+                    };
+                    let origin = origin.map(|(path, beg)| (path.clone(), beg + directive.beg));
+                    for (written, names) in directive.names {
+                        if names.is_empty() {
+                            continue;
+                        }
+                        let i = self.ctl_suppression(origin.clone(), directive.len, written);
+                        let ignored = self.ctl_ignored_lines.entry(line).or_default();
+                        ignored.extend(names.into_iter().map(|x| (x, i)));
+                    }
+                }
+            }
+        }
+    }
+
+    // Index of the suppression for a name in a control comment.
+    // Textrules and syntaxrules see the same comments separately, so `all`
+    // may already be tracked.
+    fn ctl_suppression(&mut self, origin: Option<(PathBuf, usize)>, len: usize, name: String) -> usize {
+        if origin.is_some() {
+            let found = self
+                .ctl_suppressions
+                .iter()
+                .position(|x| x.origin == origin && x.name == name);
+            if let Some(i) = found {
+                return i;
+            }
+        }
+        self.ctl_suppressions.push(CtlSuppression {
+            origin,
+            len,
+            name,
+            used: false,
+        });
+        self.ctl_suppressions.len() - 1
+    }
+
+    fn ctl_unknown_failed(&self, path: &Path, beg: usize, len: usize, name: &str) -> LintFailed {
+        let known = self.ctl_known.iter().map(|x| x.as_str());
+        let hint = match did_you_mean(name, known) {
+            Some(x) => format!("Rule `{}` is unknown, did you mean `{}`?", name, x),
+            None => format!("Rule `{}` is unknown.", name),
+        };
+        LintFailed {
+            path: path.to_path_buf(),
+            beg,
+            len,
+            name: String::from("unknown_suppression"),
+            hint,
+            reason: String::from("Control comments naming unknown rules have no effect."),
+            severity: Severity::Warning,
+            fixes: vec![],
+        }
+    }
+
+    // Control comments in the current file which name unknown rules, or which
+    // don't suppress any failure, in order of appearance.
+    // This should be called after all textrules and syntaxrules are checked.
+    pub fn ctl_failures(&mut self) -> Vec<LintFailed> {
+        let mut ret: Vec<LintFailed> = self.ctl_unknown.drain(..).collect();
+        for x in self.ctl_suppressions.drain(..) {
+            if let (false, Some((path, beg))) = (x.used, x.origin) {
+                ret.push(LintFailed {
+                    path,
+                    beg,
+                    len: x.len,
+                    hint: format!("Remove `{}` from this control comment, as it suppresses nothing.", x.name),
+                    name: String::from("unused_suppression"),
+                    reason: String::from("Control comments which suppress nothing may hide future failures."),
+                    severity: Severity::Warning,
+                    fixes: vec![],
+                });
+            }
+        }
+
+        ret.retain(|x| {
+            !self
+                .option
+                .exclude_paths
+                .iter()
+                .any(|exclude| exclude.is_match(&x.path.to_string_lossy()))
+        });
+        ret.sort_by(|a, b| (&a.path, a.beg).cmp(&(&b.path, b.beg)));
+        ret
+    }
+
     pub fn syntaxrules_check(&mut self, syntax_tree: &SyntaxTree, event: &NodeEvent) -> Vec<LintFailed> {
         self.update_ctl_enabled(syntax_tree, event);
 
@@ -235,16 +720,32 @@ impl Linter {
 
         let mut ret = Vec::new();
         'outer: for rule in &mut self.syntaxrules {
-            match self.ctl_enabled[&rule.name()] {
-                true => {}
-                _ => {
+            // Where a rule is disabled, it's only checked to see whether the
+            // control comment suppresses anything, i.e. when unused
+            // suppressions are reported, or to count the failures suppressed
+            // by a comment naming that rule, but not those disabled by `all`.
+            if !self.ctl_enabled[&rule.name()] {
+                let named = matches!(
+                    self.ctl_off.get(&rule.name()),
+                    Some(i) if self.ctl_suppressions[*i].name == rule.name()
+                );
+                if !self.ctl_report_unused && !named {
+                    // Custom rules track their position in the syntax tree,
+                    // so are given every node, but their failures dropped.
+                    if self.custom_syntaxrules.contains(&rule.name()) {
+                        rule.check(syntax_tree, event, &self.option);
+                    }
                     continue 'outer;
                 }
             }
 
             match rule.check(syntax_tree, event, &self.option) {
                 SyntaxRuleResult::Fail => {
-                    if let Some((path, beg)) = syntax_tree.get_origin(&locate) {
+                    if ctl_suppressed(&mut self.ctl_suppressions, &self.ctl_off, &self.ctl_ignored_lines, &rule.name(), locate.line) {
+                        self.ctl_n_suppressed += 1;
+                        continue 'outer;
+                    }
+                    if let Some((path, beg)) = syntax_tree.get_origin(locate) {
                         for exclude in &self.option.exclude_paths {
                             if exclude.is_match(&path.to_string_lossy()) {
                                 continue 'outer;
@@ -257,12 +758,18 @@ impl Linter {
                             name: rule.name(),
                             hint: rule.hint(&self.option),
                             reason: rule.reason(),
+                            severity: self.severities[&rule.name()],
+                            fixes: vec![],
                         };
                         ret.push(result);
                     }
                 }
                 SyntaxRuleResult::FailAt(offset, len) => {
-                    if let Some((path, beg)) = syntax_tree.get_origin(&locate) {
+                    if ctl_suppressed(&mut self.ctl_suppressions, &self.ctl_off, &self.ctl_ignored_lines, &rule.name(), locate.line) {
+                        self.ctl_n_suppressed += 1;
+                        continue 'outer;
+                    }
+                    if let Some((path, beg)) = syntax_tree.get_origin(locate) {
                         for exclude in &self.option.exclude_paths {
                             if exclude.is_match(&path.to_string_lossy()) {
                                 continue 'outer;
@@ -275,11 +782,45 @@ impl Linter {
                             name: rule.name(),
                             hint: rule.hint(&self.option),
                             reason: rule.reason(),
+                            severity: self.severities[&rule.name()],
+                            fixes: vec![],
+                        };
+                        ret.push(result);
+                    }
+                }
+                SyntaxRuleResult::FailFix(fixes) => {
+                    if ctl_suppressed(&mut self.ctl_suppressions, &self.ctl_off, &self.ctl_ignored_lines, &rule.name(), locate.line) {
+                        self.ctl_n_suppressed += 1;
+                        continue 'outer;
+                    }
+                    if let Some((path, beg)) = syntax_tree.get_origin(locate) {
+                        for exclude in &self.option.exclude_paths {
+                            if exclude.is_match(&path.to_string_lossy()) {
+                                continue 'outer;
+                            }
+                        }
+                        let fixes = fixes
+                            .iter()
+                            .filter_map(|(x, text)| origin_fix(syntax_tree, path, x, text))
+                            .collect();
+                        let result = LintFailed {
+                            path: path.clone(),
+                            beg,
+                            len: locate.len,
+                            name: rule.name(),
+                            hint: rule.hint(&self.option),
+                            reason: rule.reason(),
+                            severity: self.severities[&rule.name()],
+                            fixes,
                         };
                         ret.push(result);
                     }
                 }
                 SyntaxRuleResult::FailLocate(x) => {
+                    if ctl_suppressed(&mut self.ctl_suppressions, &self.ctl_off, &self.ctl_ignored_lines, &rule.name(), x.line) {
+                        self.ctl_n_suppressed += 1;
+                        continue 'outer;
+                    }
                     if let Some((path, beg)) = syntax_tree.get_origin(&x) {
                         for exclude in &self.option.exclude_paths {
                             if exclude.is_match(&path.to_string_lossy()) {
@@ -293,6 +834,8 @@ impl Linter {
                             name: rule.name(),
                             hint: rule.hint(&self.option),
                             reason: rule.reason(),
+                            severity: self.severities[&rule.name()],
+                            fixes: vec![],
                         };
                         ret.push(result);
                     }
@@ -304,6 +847,184 @@ impl Linter {
     }
 }
 
+// Parse each control comment in `text`, where `all` is expanded to every rule
+// in `rules` and any other names not in `rules` are expanded to nothing.
+fn ctl_directives<V>(
+    re_ctl: &Regex,
+    text: &str,
+    rules: &HashMap<String, V>,
+) -> Vec<CtlDirective> {
+    let mut ret = Vec::new();
+    for caps in re_ctl.captures_iter(text) {
+        let action = match caps.get(1).unwrap().as_str() {
+            "on" => CtlAction::On,
+            "off" => CtlAction::Off,
+            "off-next-line" => CtlAction::OffNextLine,
+            _ => CtlAction::Ignore,
+        };
+
+        let mut names = Vec::new();
+        for name in caps.get(2).unwrap().as_str().split(',').map(|x| x.trim()) {
+            let expanded = if name == "all" {
+                rules.keys().cloned().collect()
+            } else if rules.contains_key(name) {
+                vec![name.to_string()]
+            } else {
+                vec![]
+            };
+            names.push((name.to_string(), expanded));
+        }
+
+        let m = caps.get(0).unwrap();
+        ret.push(CtlDirective {
+            action,
+            beg: m.start(),
+            len: m.len(),
+            names,
+        });
+    }
+    ret
+}
+
+// Check whether a syntaxrule's failure is suppressed by a control comment,
+// marking every suppression which applies as used.
+fn ctl_suppressed(
+    suppressions: &mut [CtlSuppression],
+    ctl_off: &HashMap<String, usize>,
+    ctl_ignored_lines: &HashMap<u32, Vec<(String, usize)>>,
+    name: &str,
+    line: u32,
+) -> bool {
+    let mut ret = false;
+    if let Some(i) = ctl_off.get(name) {
+        suppressions[*i].used = true;
+        ret = true;
+    }
+    for (x, i) in ctl_ignored_lines.get(&line).into_iter().flatten() {
+        if x == name {
+            suppressions[*i].used = true;
+            ret = true;
+        }
+    }
+    ret
+}
+
+// The most similar of `candidates` to a misspelt `name`, if any is similar
+// enough to be a likely suggestion.
+pub fn did_you_mean<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .map(|x| (strsim::jaro_winkler(name, x), x))
+        .filter(|(similarity, _)| *similarity > 0.8)
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap().then_with(|| b.1.cmp(a.1)))
+        .map(|(_, x)| x)
+}
+
+// Map a fix from the syntax tree's text to the original source file `path`.
+// Fixes are only usable where the span is contiguous in that file, i.e. not
+// within or across macro expansions or included files.
+fn origin_fix(syntax_tree: &SyntaxTree, path: &Path, locate: &Locate, text: &str) -> Option<Fix> {
+    let beg = if locate.len > 0 {
+        let (beg_path, beg) = syntax_tree.get_origin(locate)?;
+        let last = Locate { offset: locate.offset + locate.len - 1, ..*locate };
+        let (last_path, last) = syntax_tree.get_origin(&last)?;
+        if beg_path != path || last_path != path || last != beg + locate.len - 1 {
+            return None;
+        }
+        beg
+    } else {
+        // Insertions are anchored to the preceding character.
+        let prev = Locate { offset: locate.offset.checked_sub(1)?, ..*locate };
+        let (prev_path, prev) = syntax_tree.get_origin(&prev)?;
+        if prev_path != path {
+            return None;
+        }
+        prev + 1
+    };
+
+    Some(Fix {
+        beg,
+        len: locate.len,
+        text: text.to_string(),
+    })
+}
+
+// Text of a source file, decoded from whichever encoding it appears to use,
+// e.g. GBK, so that offsets into the text are the same wherever it's read.
+pub fn read_source(path: &Path) -> Result<String, Error> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::new();
+
+    file.read_to_end(&mut buffer)?;
+    Ok(decode_source(&buffer))
+}
+
+pub fn decode_source(buffer: &[u8]) -> String {
+    let mut detector = EncodingDetector::new();
+    detector.feed(buffer, true);
+    let encoding = detector.guess(None, true).decode(buffer).0;
+
+    encoding.into_owned()
+}
+
+// Apply fixes, collected from all failures in one file, to that file's text.
+// Any fix which overlaps a previously applied fix is skipped, so applying the
+// remaining fixes requires another run.
+// Returns the fixed text and the number of fixes applied.
+pub fn apply_fixes(text: &str, fixes: &[Fix]) -> (String, usize) {
+    let mut fixes: Vec<&Fix> = fixes.iter().collect();
+    fixes.sort_by_key(|x| (x.beg, x.len));
+
+    // Identical fixes arise from a header which is included by several files.
+    fixes.dedup();
+
+    let mut ret = String::with_capacity(text.len());
+    let mut pos = 0;
+    let mut n_applied = 0;
+    for fix in fixes {
+        let end = fix.beg + fix.len;
+        if fix.beg < pos
+            || end > text.len()
+            || !text.is_char_boundary(fix.beg)
+            || !text.is_char_boundary(end)
+        {
+            continue;
+        }
+        ret.push_str(&text[pos..fix.beg]);
+        ret.push_str(&fix.text);
+        pos = end;
+        n_applied += 1;
+    }
+    ret.push_str(&text[pos..]);
+
+    (ret, n_applied)
+}
+
+// Utility function used by syntaxrules `style_keyword_*`.
+// Where a keyword is followed only by spaces or tabs, those may be replaced
+// with `succ` to fix the failure.
+// Otherwise, e.g. where the keyword is followed by a newline or comment, there
+// is no unambiguous fix.
+pub fn fail_keyword_succ(
+    keyword: &Keyword,
+    syntax_tree: &SyntaxTree,
+    succ: &str,
+) -> SyntaxRuleResult {
+    let loc: &Locate = &keyword.nodes.0;
+    let t: &str = syntax_tree.get_str(keyword).unwrap();
+    let ws: &str = &t[loc.len..];
+
+    if ws.chars().all(|c| c == ' ' || c == '\t') {
+        let fix = Locate {
+            offset: loc.offset + loc.len,
+            line: loc.line,
+            len: ws.len(),
+        };
+        SyntaxRuleResult::FailFix(vec![(fix, String::from(succ))])
+    } else {
+        SyntaxRuleResult::Fail
+    }
+}
+
 // Utility function used by syntaxrules `re_(required|forbidden)_*`.
 pub fn check_regex(
     required_not_forbidden: bool,
@@ -0,0 +1,280 @@
+use crate::config::Severity;
+use crate::linter::LintFailed;
+use crate::printer::ParseFailed;
+use anyhow::{Context, Error};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+// Results of linting one open document, where `log` is any other output,
+// e.g. warnings about the configuration, which is shown to the user.
+#[derive(Debug, Default)]
+pub struct DocumentResult {
+    pub failed: Vec<LintFailed>,
+    pub parse_failed: Vec<ParseFailed>,
+    pub log: Vec<String>,
+}
+
+// Serve the Language Server Protocol over `reader` and `writer`, i.e. stdin
+// and stdout, until the client exits.
+// Documents are linted by `lint` whenever they're opened, changed, or saved,
+// using the text held by the client, which may differ from the file.
+// Returns true if the client requested shutdown before exiting, as the exit
+// status must show otherwise.
+pub fn serve<R, W, F>(mut reader: R, mut writer: W, mut lint: F) -> Result<bool, Error>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(&Path, &str) -> Result<DocumentResult, Error>,
+{
+    let mut documents: HashMap<String, String> = HashMap::new();
+    let mut shutdown = false;
+
+    while let Some(message) = read_message(&mut reader)? {
+        let message = match message {
+            Ok(x) => x,
+            Err(x) => {
+                let error = json!({"code": -32700, "message": x.to_string()});
+                send(&mut writer, json!({"jsonrpc": "2.0", "id": null, "error": error}))?;
+                continue;
+            }
+        };
+
+        // Messages without a method are responses, and none are expected.
+        let method = match message.get("method").and_then(|x| x.as_str()) {
+            Some(x) => x,
+            None => continue,
+        };
+        let id = message.get("id").cloned();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+
+        let result = match method {
+            "exit" => return Ok(shutdown),
+            _ if shutdown => Err((-32600, String::from("server is shut down"))),
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": {
+                        "openClose": true,
+                        "change": 1, // Full text of the document on each change.
+                        "save": {"includeText": false},
+                    },
+                },
+                "serverInfo": {"name": "svlint", "version": env!("CARGO_PKG_VERSION")},
+            })),
+            "shutdown" => {
+                shutdown = true;
+                Ok(Value::Null)
+            }
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                documents.insert(uri.clone(), text.to_string());
+                publish(&mut writer, &mut lint, &uri, text)?;
+                Ok(Value::Null)
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|x| x.last()).and_then(|x| x["text"].as_str()) {
+                    documents.insert(uri.clone(), text.to_string());
+                    publish(&mut writer, &mut lint, &uri, text)?;
+                }
+                Ok(Value::Null)
+            }
+            // Files included by the document may have been saved too.
+            "textDocument/didSave" => {
+                if let Some(text) = documents.get(&uri) {
+                    publish(&mut writer, &mut lint, &uri, text)?;
+                }
+                Ok(Value::Null)
+            }
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                send_diagnostics(&mut writer, &uri, vec![])?;
+                Ok(Value::Null)
+            }
+            _ => Err((-32601, format!("method not found: {}", method))),
+        };
+
+        // Notifications, which have no `id`, are never answered.
+        if let Some(id) = id {
+            let response = match result {
+                Ok(x) => json!({"jsonrpc": "2.0", "id": id, "result": x}),
+                Err((code, x)) => json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": x}}),
+            };
+            send(&mut writer, response)?;
+        }
+    }
+
+    Ok(false)
+}
+
+// Each message is a JSON-RPC object preceded by headers, of which only
+// `Content-Length` is needed.
+// Returns `None` at the end of input, or an inner error where the message
+// isn't valid JSON, which is reported to the client.
+fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Result<Value, Error>>, Error> {
+    let mut len: Option<usize> = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if len.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((k, v)) = line.split_once(':') {
+            if k.trim().eq_ignore_ascii_case("content-length") {
+                len = Some(v.trim().parse().context("invalid Content-Length")?);
+            }
+        }
+    }
+
+    let mut buf = vec![0; len.unwrap()];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(serde_json::from_slice(&buf).map_err(Error::from)))
+}
+
+fn send<W: Write>(writer: &mut W, message: Value) -> Result<(), Error> {
+    let s = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{}", s.len(), s)?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn send_diagnostics<W: Write>(writer: &mut W, uri: &str, diagnostics: Vec<Value>) -> Result<(), Error> {
+    send(
+        writer,
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": {"uri": uri, "diagnostics": diagnostics},
+        }),
+    )
+}
+
+// Lint a document and publish its diagnostics, or show why it couldn't be
+// linted, e.g. an invalid configuration.
+fn publish<W, F>(writer: &mut W, lint: &mut F, uri: &str, text: &str) -> Result<(), Error>
+where
+    W: Write,
+    F: FnMut(&Path, &str) -> Result<DocumentResult, Error>,
+{
+    let path = uri_to_path(uri).unwrap_or_else(|| PathBuf::from(uri));
+    let result = match lint(&path, text) {
+        Ok(x) => x,
+        Err(x) => {
+            return send(
+                writer,
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "window/showMessage",
+                    "params": {"type": 1, "message": format!("svlint: {:#}", x)},
+                }),
+            );
+        }
+    };
+
+    for x in &result.log {
+        send(
+            writer,
+            json!({
+                "jsonrpc": "2.0",
+                "method": "window/showMessage",
+                "params": {"type": 2, "message": format!("svlint: {}", x)},
+            }),
+        )?;
+    }
+
+    // Failures at positions in included files can't be shown in this
+    // document.
+    let mut diagnostics = Vec::new();
+    for failed in result.failed.iter().filter(|x| x.path == path) {
+        let severity = match failed.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+            _ => 3,
+        };
+        diagnostics.push(json!({
+            "range": range(text, failed.beg, failed.beg + failed.len),
+            "severity": severity,
+            "code": failed.name,
+            "source": "svlint",
+            "message": format!("{}\nreason: {}", failed.hint, failed.reason),
+            "data": {"name": failed.name, "hint": failed.hint, "reason": failed.reason},
+        }));
+    }
+
+    // Errors which aren't at a position in this document are shown at its
+    // start, as it can't be parsed.
+    for failed in &result.parse_failed {
+        let (beg, message) = match (&failed.path, failed.beg) {
+            (Some(x), Some(beg)) if x == &path => (beg, failed.message.clone()),
+            (Some(x), _) => (0, format!("{} in '{}'", failed.message, x.display())),
+            (None, _) => (0, failed.message.clone()),
+        };
+        diagnostics.push(json!({
+            "range": range(text, beg, beg + 1),
+            "severity": 1,
+            "code": failed.kind,
+            "source": "svlint",
+            "message": message,
+        }));
+    }
+
+    send_diagnostics(writer, uri, diagnostics)
+}
+
+// Range between byte offsets, where positions are given by line and UTF-16
+// code unit, as required by LSP.
+fn range(text: &str, beg: usize, end: usize) -> Value {
+    json!({"start": position(text, beg), "end": position(text, end)})
+}
+
+fn position(text: &str, offset: usize) -> Value {
+    let offset = (0..=offset.min(text.len()))
+        .rev()
+        .find(|x| text.is_char_boundary(*x))
+        .unwrap_or(0);
+    let line_beg = text[..offset].rfind('\n').map_or(0, |x| x + 1);
+    json!({
+        "line": text[..offset].matches('\n').count(),
+        "character": text[line_beg..offset].encode_utf16().count(),
+    })
+}
+
+// Path of a `file:` URI, with percent-encoded bytes decoded.
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let s = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        let decoded = match (s[i], s.get(i + 1..i + 3)) {
+            (b'%', Some(x)) => std::str::from_utf8(x).ok().and_then(|x| u8::from_str_radix(x, 16).ok()),
+            _ => None,
+        };
+        match decoded {
+            Some(x) => {
+                bytes.push(x);
+                i += 3;
+            }
+            None => {
+                bytes.push(s[i]);
+                i += 1;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+
+    // Windows paths are given as `file:///C:/...`.
+    let path = match path.strip_prefix('/') {
+        Some(x) if cfg!(windows) && x.as_bytes().get(1) == Some(&b':') => x.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
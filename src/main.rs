@@ -1,19 +1,31 @@
-use anyhow::{Context, Error};
-use chardetng::EncodingDetector;
+use anyhow::{anyhow, Context, Error};
 use clap::{Parser, CommandFactory};
 use clap_complete;
 use enquote;
-use std::collections::HashMap;
+use similar::TextDiff;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::{self, BufRead, Read, Write};
 use std::path::{Path, PathBuf};
-use std::{env, process};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
 use sv_filelist_parser;
 use sv_parser::Error as SvParserError;
-use sv_parser::{parse_sv_str, preprocess, Define, DefineText};
-use svlint::config::Config;
-use svlint::linter::{Linter, TextRuleEvent};
-use svlint::printer::Printer;
+use sv_parser::{parse_sv_str, preprocess, preprocess_str, unwrap_locate, Define, DefineText, Defines};
+use svlint::baseline::{baseline_path, normalise_path, Baseline, Fingerprint, Fingerprinter};
+use svlint::cache::{file_identity, include_regex, included_files, Cache};
+use svlint::config::{Config, ConfigOption, Severity};
+use svlint::diff::Diff;
+use svlint::docs::{rule_category, rule_doc, ruleset_membership, rulesets_enabling, testcases, RULE_DOCS};
+use svlint::linter::{
+    apply_fixes, decode_source, did_you_mean, read_source, Fix, LintFailed, Linter, TextRuleEvent,
+};
+use svlint::lsp::{serve, DocumentResult};
+use svlint::printer::{OutputFormat, ParseFailed, Printer, Summary};
+use svlint::query::Query;
+use svlint::watch::Watcher;
 
 // -------------------------------------------------------------------------------------------------
 // Opt
@@ -30,14 +42,19 @@ pub enum DumpFilelistMode {
 #[clap(name = "svlint")]
 #[clap(long_version(option_env!("LONG_VERSION").unwrap_or(env!("CARGO_PKG_VERSION"))))]
 pub struct Opt {
-    /// Source file(s)
-    #[clap(required_unless_present_any = &["filelists", "config-example", "config-update", "shell-completion"])]
+    /// Source file(s), where `-` is text read from stdin
+    #[clap(required_unless_present_any = &["filelists", "config-example", "config-update", "shell-completion", "explain", "list-rules", "lsp"])]
     pub files: Vec<PathBuf>,
 
     /// Filelist file(s)
     #[clap(short = 'f', long = "filelist", conflicts_with = "files")]
     pub filelists: Vec<PathBuf>,
 
+    /// Path of the file whose text is read from stdin by `-`, used to match
+    /// `exclude_paths`, by rules which check the filename, and in reports
+    #[clap(long = "stdin-filename", value_name = "PATH", default_value = "<stdin>")]
+    pub stdin_filename: PathBuf,
+
     /// Define macro for preprocessor, e.g. `-D FOO` or `-D FOO=123`
     #[clap(
         short = 'D',
@@ -92,6 +109,59 @@ pub struct Opt {
     #[clap(long = "github-actions")]
     pub github_actions: bool,
 
+    /// Output format, where machine-readable formats are printed as a single
+    /// report after all files are processed
+    #[clap(value_enum, long = "format", default_value = "text")]
+    pub format: OutputFormat,
+
+    /// Exit with failure status when any rule with severity "warning" fails
+    #[clap(long = "deny-warnings")]
+    pub deny_warnings: bool,
+
+    /// Exit with failure status when there are more than N warnings
+    #[clap(long = "max-warnings", value_name = "N")]
+    pub max_warnings: Option<usize>,
+
+    /// Apply fixes for rule failures to source files in-place
+    #[clap(long = "fix")]
+    pub fix: bool,
+
+    /// Print fixes for rule failures as a unified diff, without changing files
+    #[clap(long = "fix-dry-run", conflicts_with = "fix")]
+    pub fix_dry_run: bool,
+
+    /// Suppress rule failures which are recorded in a baseline file
+    #[clap(long = "baseline", value_name = "FILE")]
+    pub baseline: Option<PathBuf>,
+
+    /// Record all rule failures in a baseline file, instead of reporting them
+    #[clap(long = "write-baseline", value_name = "FILE", conflicts_with = "baseline")]
+    pub write_baseline: Option<PathBuf>,
+
+    /// Report only rule failures on lines added or modified by a unified
+    /// diff, e.g. from `git diff`
+    #[clap(long = "diff-file", value_name = "PATCH", conflicts_with = "write-baseline")]
+    pub diff_file: Option<PathBuf>,
+
+    /// Report only rule failures on lines changed since a git revision,
+    /// e.g. `--diff-base origin/main`
+    #[clap(
+        long = "diff-base",
+        value_name = "REF",
+        conflicts_with_all = &["diff-file", "write-baseline"]
+    )]
+    pub diff_base: Option<String>,
+
+    /// Warn about control comments which name unknown rules or don't
+    /// suppress any failure
+    #[clap(long = "report-unused-suppressions")]
+    pub report_unused_suppressions: bool,
+
+    /// Print a summary of the run, with counts of failures per rule, file,
+    /// and directory
+    #[clap(long = "summary", alias = "stats")]
+    pub summary: bool,
+
     /// Update TOML configuration file in-place
     #[clap(long = "config-update", alias = "update")]
     pub config_update: bool,
@@ -104,14 +174,57 @@ pub struct Opt {
     #[clap(value_enum, long = "dump-filelist")]
     pub dump_filelist: Option<DumpFilelistMode>,
 
+    /// Print documentation of a rule, with examples and the rulesets which
+    /// enable it
+    #[clap(long = "explain", value_name = "RULE")]
+    pub explain: Option<String>,
+
+    /// Print all rules, including those from plugins, with their severity
+    /// under the configuration, category, options, and rulesets
+    #[clap(long = "list-rules")]
+    pub list_rules: bool,
+
     /// Print shell completion script
     #[clap(value_enum, long = "shell-completion")]
     pub shell_completion: Option<clap_complete::Shell>,
 
+    /// Number of files to process in parallel, or 0 to use all available CPUs
+    #[clap(short = 'j', long = "jobs", default_value = "1")]
+    pub jobs: usize,
+
+    /// Process files in order as a single compilation unit, where macros
+    /// defined in one file are visible in the following files
+    #[clap(long = "compilation-unit")]
+    pub compilation_unit: bool,
+
+    /// Cache results, so that files are only linted again when they, or
+    /// anything else affecting their results, change
+    #[clap(long = "cache")]
+    pub cache: bool,
+
+    /// Directory where results are cached, implying --cache [default: .svlint-cache]
+    #[clap(long = "cache-dir", value_name = "DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Lint again whenever any linted file, included file, filelist, or the
+    /// configuration changes, until interrupted
+    #[clap(long = "watch")]
+    pub watch: bool,
+
+    /// Run as a language server, speaking the Language Server Protocol over
+    /// stdin and stdout
+    #[clap(long = "lsp")]
+    pub lsp: bool,
+
     /// Print syntax trees, useful for debug or syntax analysis
     #[clap(long = "dump-syntaxtree")]
     pub dump_syntaxtree: bool,
 
+    /// Print the location of each syntax tree node matching a query, e.g.
+    /// `--query 'AlwaysConstruct LoopStatement'`, instead of linting
+    #[clap(long = "query", value_name = "QUERY")]
+    pub query: Option<Query>,
+
     /// Print preprocessor output then exit before parsing syntax
     #[clap(short = 'E', long = "preprocess-only")]
     pub preprocess_only: bool,
@@ -123,8 +236,9 @@ pub struct Opt {
 
 #[cfg_attr(tarpaulin, skip)]
 pub fn main() {
-    let opt = Parser::parse();
+    let opt: Opt = Parser::parse();
     let mut printer = Printer::new(false);
+    printer.set_format(opt.format);
     let exit_code = match run_opt(&mut printer, &opt) {
         Ok(pass) => {
             if pass {
@@ -151,39 +265,66 @@ pub fn run_opt(printer: &mut Printer, opt: &Opt) -> Result<bool, Error> {
         return Ok(true);
     }
 
+    if let Some(name) = &opt.explain {
+        explain(printer, name)?;
+        return Ok(true);
+    }
+
     if let Some(generator) = opt.shell_completion {
         let mut cmd = Opt::command();
         shell_completion(generator, &mut cmd);
         return Ok(true);
     }
 
-    let config = search_config(printer, &opt.config);
+    if opt.lsp {
+        return run_lsp(io::stdin().lock(), io::stdout().lock(), opt);
+    }
+
+    let config_path = search_config(printer, &opt.config);
 
-    let config = if let Some(config) = config {
-        let mut f = File::open(&config)
+    // Updates apply only to the file itself, not to any configurations which
+    // it extends.
+    if let (Some(config), true) = (&config_path, opt.config_update) {
+        let mut f = File::open(config)
             .with_context(|| format!("failed to open '{}'", config.to_string_lossy()))?;
         let mut s = String::new();
         let _ = f.read_to_string(&mut s);
         let mut ret: Config = toml::from_str(&s)
             .with_context(|| format!("failed to parse toml '{}'", config.to_string_lossy()))?;
 
-        if opt.config_update {
-            ret.migrate();
-            let mut f = OpenOptions::new()
-                .write(true)
-                .open(&config)
-                .with_context(|| format!("failed to open '{}'", config.to_string_lossy()))?;
-            write!(f, "{}", toml::to_string(&ret).unwrap())
-                .with_context(|| format!("failed to write '{}'", config.to_string_lossy()))?;
-            return Ok(true);
-        }
+        ret.migrate();
+        let mut f = OpenOptions::new()
+            .write(true)
+            .open(config)
+            .with_context(|| format!("failed to open '{}'", config.to_string_lossy()))?;
+        write!(f, "{}", toml::to_string(&ret).unwrap())
+            .with_context(|| format!("failed to write '{}'", config.to_string_lossy()))?;
+        return Ok(true);
+    }
+
+    if opt.watch {
+        return watch(printer, opt, config_path.as_deref());
+    }
 
-        ret
+    let (config, _) = load_config(printer, opt, config_path.as_deref())?;
+    run_opt_config(printer, opt, config)
+}
+
+// The configuration, with every file it was read from, i.e. the file found by
+// `search_config()` and those it extends.
+#[cfg_attr(tarpaulin, skip)]
+fn load_config(
+    printer: &mut Printer,
+    opt: &Opt,
+    config_path: Option<&Path>,
+) -> Result<(Config, Vec<PathBuf>), Error> {
+    let ret = if let Some(config) = config_path {
+        Config::load_with_files(config)?
     } else {
-        if !opt.plugins.is_empty() {
+        let config = if !opt.plugins.is_empty() {
             Config::new()
         } else {
-            if !opt.silent && opt.dump_filelist.is_none() && !opt.preprocess_only {
+            if !opt.silent && opt.dump_filelist.is_none() && !opt.preprocess_only && opt.query.is_none() {
                 let msg = format!(
                     "Config file '{}' is not found. Enable all rules",
                     opt.config.to_string_lossy()
@@ -191,31 +332,74 @@ pub fn run_opt(printer: &mut Printer, opt: &Opt) -> Result<bool, Error> {
                 printer.print_warning(&msg)?;
             }
             Config::new().enable_all()
-        }
+        };
+        (config, vec![])
     };
 
-    run_opt_config(printer, opt, config)
+    Ok(ret)
 }
 
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
+
+// Lint repeatedly, each time any file which affects the results changes,
+// until interrupted.
+// With `--cache`, results of unchanged files are replayed from the cache, so
+// only the files affected by each change are parsed again.
 #[cfg_attr(tarpaulin, skip)]
-pub fn run_opt_config(printer: &mut Printer, opt: &Opt, config: Config) -> Result<bool, Error> {
-    let mut not_obsolete = true;
-    for (org_rule, renamed_rule) in config.check_rename() {
-        let msg = format!(
-            "Rule \"{}\" is obsolete. Please rename to \"{}\"",
-            org_rule, renamed_rule,
-        );
-        printer.print_warning(&msg)?;
-        not_obsolete = false;
+fn watch(printer: &mut Printer, opt: &Opt, config_path: Option<&Path>) -> Result<bool, Error> {
+    if opt.format != OutputFormat::Text {
+        return Err(anyhow!("--watch supports only the text format"));
+    }
+    if opt.files.iter().any(|x| x == Path::new("-")) {
+        return Err(anyhow!("--watch can't be used with stdin"));
     }
 
-    let mut linter = Linter::new(config);
-    for plugin in &opt.plugins {
-        linter.load(&plugin)?;
+    let mut watcher = Watcher::default();
+    let mut config: Option<Config> = None;
+    let mut config_files: Vec<PathBuf> = config_path.into_iter().map(|x| x.to_path_buf()).collect();
+    loop {
+        printer.clear_screen();
+
+        // Errors, e.g. in a configuration which is being edited, are
+        // reported in place of the results until the next change.
+        // Until the configuration can be loaded again, the files which it
+        // previously extended are still watched.
+        if config.is_none() {
+            match load_config(printer, opt, config_path) {
+                Ok((x, files)) => {
+                    config = Some(x);
+                    config_files = files;
+                }
+                Err(x) => printer.print_error_type(x)?,
+            }
+        }
+        if let Some(config) = &config {
+            if let Err(x) = run_opt_config(printer, opt, config.clone()) {
+                printer.print_error_type(x)?;
+            }
+        }
+
+        watcher.watch(watched_files(opt, &config_files));
+        if !opt.silent {
+            printer.print_info(&format!(
+                "watching {} file(s) for changes, press Ctrl-C to stop",
+                watcher.paths().count()
+            ))?;
+        }
+
+        // The configuration is only loaded again when it, or any file it
+        // extends, changes.
+        let changed = watcher.wait(WATCH_INTERVAL);
+        if changed.iter().any(|x| config_files.contains(x)) {
+            config = None;
+        }
     }
+}
 
+// Macros given by `-D`, either `NAME` or `NAME=TEXT`.
+fn cli_defines(cli_defines: &[String]) -> Result<Defines, Error> {
     let mut defines = HashMap::new();
-    for define in &opt.defines {
+    for define in cli_defines {
         let mut define = define.splitn(2, '=');
         let ident = String::from(define.next().unwrap());
         let text = if let Some(x) = define.next() {
@@ -227,6 +411,152 @@ pub fn run_opt_config(printer: &mut Printer, opt: &Opt, config: Config) -> Resul
         let define = Define::new(ident.clone(), vec![], text);
         defines.insert(ident, Some(define));
     }
+    Ok(defines)
+}
+
+// Everything needed to lint a document in the language server, which is
+// found again whenever the configuration or a filelist changes.
+struct LspState {
+    linter: Linter,
+    defines: Defines,
+    incdirs: Vec<PathBuf>,
+    log: Vec<String>,
+}
+
+// Serve the Language Server Protocol, where each document is linted from the
+// text held by the client, with the configuration, include directories and
+// macros which would be used on the command line.
+// Each document is linted separately, as if with `--jobs` greater than one.
+#[cfg_attr(tarpaulin, skip)]
+fn run_lsp<R: BufRead, W: Write>(reader: R, writer: W, opt: &Opt) -> Result<bool, Error> {
+    let mut state: Option<LspState> = None;
+    let mut watcher = Watcher::default();
+
+    serve(reader, writer, |path, text| {
+        if !watcher.changed().is_empty() {
+            state = None;
+        }
+        let state = match &mut state {
+            Some(x) => x,
+            None => {
+                // Messages would be mixed with the protocol on stdout, so
+                // they're given to the client instead.
+                let mut log = Printer::new(true);
+                let config_path = search_config(&mut log, &opt.config);
+                let (config, config_files) = load_config(&mut log, opt, config_path.as_deref())?;
+                config.validate().context("invalid configuration")?;
+                let mut linter = Linter::new(config);
+                for plugin in &opt.plugins {
+                    linter.load(plugin)?;
+                }
+
+                let mut defines = cli_defines(&opt.defines)?;
+                let mut incdirs = opt.incdirs.clone();
+                for filelist in &opt.filelists {
+                    let (_, mut i, d) = parse_filelist(filelist)?;
+                    incdirs.append(&mut i);
+                    defines.extend(d);
+                }
+                let (_, incdirs) = get_files_incdirs(vec![], incdirs);
+
+                // Configurations which are extended are watched too.
+                watcher.watch(config_files.into_iter().chain(opt.filelists.iter().cloned()));
+                let log = log.read_to_string().unwrap_or_default();
+                state.insert(LspState {
+                    linter,
+                    defines,
+                    incdirs,
+                    log: log.lines().filter(|x| !x.is_empty()).map(String::from).collect(),
+                })
+            }
+        };
+
+        let result = lint_file(&mut state.linter, opt, None, path, Some(text), &state.defines, &state.incdirs)?;
+        let mut ret = DocumentResult {
+            log: std::mem::take(&mut state.log),
+            ..Default::default()
+        };
+        for output in result.outputs {
+            match output {
+                FileOutput::Failed(x) => ret.failed.push(x),
+                FileOutput::ParseFailed(x) => ret.parse_failed.push(x),
+                FileOutput::Text(_) => {}
+            }
+        }
+        Ok(ret)
+    })
+}
+
+// Files which affect the results of linting, i.e. the configuration files,
+// filelists, linted files, and the files they include.
+fn watched_files(opt: &Opt, config_files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut ret: Vec<PathBuf> = config_files.to_vec();
+
+    let mut files = opt.files.clone();
+    let mut incdirs = opt.incdirs.clone();
+    for filelist in &opt.filelists {
+        ret.push(filelist.clone());
+        if let Ok((mut f, mut i, _)) = parse_filelist(filelist) {
+            files.append(&mut f);
+            incdirs.append(&mut i);
+        }
+    }
+    let (files, incdirs) = get_files_incdirs(files, incdirs);
+
+    let re_include = include_regex();
+    for path in files {
+        if let Ok(text) = read_source(&path) {
+            ret.extend(included_files(&re_include, &text, &incdirs).into_iter().map(|(x, _)| x));
+        }
+        ret.push(path);
+    }
+    ret
+}
+
+#[cfg_attr(tarpaulin, skip)]
+pub fn run_opt_config(printer: &mut Printer, opt: &Opt, config: Config) -> Result<bool, Error> {
+    let stdin = if opt.files.iter().any(|x| x == Path::new("-")) {
+        let mut buffer = Vec::new();
+        io::stdin().read_to_end(&mut buffer).context("failed to read stdin")?;
+        Some(decode_source(&buffer))
+    } else {
+        None
+    };
+    run_opt_config_stdin(printer, opt, config, stdin.as_deref())
+}
+
+// Text read from stdin, given by `-` in place of a file, is linted as if it's
+// the text of the file given by `--stdin-filename`.
+#[cfg_attr(tarpaulin, skip)]
+fn run_opt_config_stdin(
+    printer: &mut Printer,
+    opt: &Opt,
+    config: Config,
+    stdin: Option<&str>,
+) -> Result<bool, Error> {
+    let start = Instant::now();
+    printer.set_format(opt.format);
+
+    let mut not_obsolete = true;
+    for (org_rule, renamed_rule) in config.check_rename() {
+        let msg = format!(
+            "Rule \"{}\" is obsolete. Please rename to \"{}\"",
+            org_rule, renamed_rule,
+        );
+        printer.print_warning(&msg)?;
+        not_obsolete = false;
+    }
+
+    // Overrides are applied and regexes are compiled only as each file is
+    // processed, so any problem with their values is found beforehand.
+    config.validate().context("invalid configuration")?;
+
+    if opt.list_rules {
+        list_rules(printer, opt, &config)?;
+        return Ok(true);
+    }
+
+    let mut defines = cli_defines(&opt.defines)?;
 
     let (files, incdirs) = if !opt.filelists.is_empty() {
         let mut files = opt.files.clone();
@@ -254,177 +584,702 @@ pub fn run_opt_config(printer: &mut Printer, opt: &Opt, config: Config) -> Resul
         return Ok(true);
     }
 
-    let mut all_pass = true;
-
-    for path in &files {
-        let mut pass = true;
-        if opt.preprocess_only {
-            match preprocess(&path, &defines, &incdirs, false, opt.ignore_include) {
-                Ok((text, new_defines)) => {
-                    let msg = format!("{}", text.text());
-                    printer.print(&msg)?;
-                    defines = new_defines;
-                }
-                Err(x) => {
-                    print_parser_error(printer, x, opt.oneline)?;
-                    pass = false;
-                }
-            }
-        } else {
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .map(|x| if x == Path::new("-") { opt.stdin_filename.clone() } else { x })
+        .collect();
 
-            // Signal beginning of file to all TextRules, which *may* be used
-            // by textrules to reset their internal state.
-            let _ = linter.textrules_check(TextRuleEvent::StartOfFile, &path, &0);
+    if let Some(query) = &opt.query {
+        return run_query(printer, opt, query, &files, stdin, defines, &incdirs);
+    }
 
-            let mut file = File::open(&path)?;
-            let mut buffer = Vec::new();
+    if let Some(s) = stdin {
+        // There's no file to which fixes could be applied.
+        if opt.fix || opt.fix_dry_run {
+            return Err(anyhow!("--fix and --fix-dry-run can't be used with stdin"));
+        }
+        printer.set_source(&opt.stdin_filename, s);
+    }
 
-            file.read_to_end(&mut buffer)?;
-            let mut detector = EncodingDetector::new();
-            detector.feed(&buffer, true);
-            let encoding = detector.guess(None, true).decode(&buffer).0;
+    // Each thread has its own set of rules, as rules keep state while
+    // processing a file.
+    // Macros defined in one file can only be carried into the next when files
+    // are processed in order.
+    let jobs = match opt.jobs {
+        0 => thread::available_parallelism().map_or(1, |x| x.get()),
+        x => x,
+    };
+    let jobs = if opt.compilation_unit { 1 } else { jobs.min(files.len()).max(1) };
+    let mut linters = Vec::new();
+    for _ in 0..jobs {
+        let mut linter = Linter::new(config.clone());
+        linter.set_report_unused_suppressions(opt.report_unused_suppressions);
+        for plugin in &opt.plugins {
+            linter.load(plugin)?;
+        }
+        linters.push(linter);
+    }
 
-            let text = encoding.into_owned();
-            let mut beg: usize = 0;
+    // Results are cached only where requested, and where nothing other than
+    // failures is printed.
+    let cache_dir = match &opt.cache_dir {
+        Some(x) => Some(x.clone()),
+        None if opt.cache => Some(PathBuf::from(".svlint-cache")),
+        None => None,
+    };
+    let cache = if let (Some(cache_dir), false, false) = (cache_dir, opt.preprocess_only, opt.dump_syntaxtree) {
+        let mut inputs = vec![
+            toml::to_string(&config)?,
+            format!("{:?} {} {}", incdirs, opt.ignore_include, opt.report_unused_suppressions),
+        ];
+        if let Ok(exe) = env::current_exe() {
+            inputs.push(file_identity(&exe));
+        }
+        for plugin in &opt.plugins {
+            inputs.push(file_identity(plugin));
+        }
+        Some(Cache::new(&cache_dir, &inputs))
+    } else {
+        None
+    };
 
-            // Iterate over lines in the file, applying each textrule to each
-            // line in turn.
-            for line in text.split_inclusive('\n') {
-                let line_stripped = line.trim_end_matches(&['\n', '\r']);
+    let mut all_pass = true;
+    let mut state = RunState::default();
+    if let Some(baseline) = &opt.baseline {
+        state.baseline = Some(Baseline::load(baseline)?);
+    }
+    if let Some(path) = &opt.diff_file {
+        state.diff = Some(Diff::load(path)?);
+    } else if let Some(base) = &opt.diff_base {
+        state.diff = Some(Diff::from_git(base)?);
+    }
+    if let Some(s) = stdin {
+        state.fingerprinter.set_source(&opt.stdin_filename, s);
+        if let Some(diff) = &mut state.diff {
+            diff.set_source(&opt.stdin_filename, s);
+        }
+    }
 
-                for failed in linter.textrules_check(TextRuleEvent::Line(&line_stripped), &path, &beg) {
-                    pass = false;
-                    if !opt.silent {
-                        printer.print_failed(&failed, opt.oneline, opt.github_actions)?;
-                    }
+    if jobs == 1 {
+        let linter = &mut linters[0];
+        for path in &files {
+            let text = stdin.filter(|_| path == &opt.stdin_filename);
+            let mut result = lint_file(linter, opt, cache.as_ref(), path, text, &defines, &incdirs)?;
+            if let Some(new_defines) = result.defines.take() {
+                if opt.compilation_unit {
+                    defines = new_defines;
                 }
-                beg += line.len();
             }
-
-            match parse_sv_str(text.as_str(), &path, &defines, &incdirs, opt.ignore_include, false) {
-                Ok((syntax_tree, new_defines)) => {
-
-                    // Iterate over nodes in the concrete syntax tree, applying
-                    // each syntaxrule to each node in turn.
-                    for node in syntax_tree.into_iter().event() {
-                        for failed in linter.syntaxrules_check(&syntax_tree, &node) {
-                            pass = false;
-                            if !opt.silent {
-                                printer.print_failed(&failed, opt.oneline, opt.github_actions)?;
-                            }
-                        }
+            all_pass &= print_file_result(printer, opt, path, result, &mut state)?;
+        }
+    } else {
+        // Workers stop taking files once a result can't be printed.
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let (tx, rx) = mpsc::channel();
+
+        thread::scope(|s| -> Result<(), Error> {
+            for linter in &mut linters {
+                let tx = tx.clone();
+                let (next, stop, files, defines, incdirs) = (&next, &stop, &files, &defines, &incdirs);
+                let cache = cache.as_ref();
+                s.spawn(move || loop {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
                     }
-                    defines = new_defines;
-
-                    if opt.dump_syntaxtree {
-                        let msg = format!("{:?}", &syntax_tree);
-                        printer.println(&msg)?;
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= files.len() {
+                        break;
                     }
-                }
-                Err(x) => {
-                    print_parser_error(printer, x, opt.oneline)?;
-                    pass = false;
+                    let text = stdin.filter(|_| files[i] == opt.stdin_filename);
+                    let result = lint_file(linter, opt, cache, &files[i], text, defines, incdirs);
+                    if tx.send((i, result)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            // Results are printed in the same order as the files, regardless of
+            // which thread finishes first.
+            let mut pending = BTreeMap::new();
+            let mut i_print = 0;
+            for (i, result) in rx.iter() {
+                pending.insert(i, result);
+                while let Some(result) = pending.remove(&i_print) {
+                    let path = &files[i_print];
+                    match result.and_then(|x| print_file_result(printer, opt, path, x, &mut state)) {
+                        Ok(x) => all_pass &= x,
+                        Err(x) => {
+                            stop.store(true, Ordering::Relaxed);
+                            return Err(x);
+                        }
+                    }
+                    i_print += 1;
                 }
             }
-        }
 
-        if opt.verbose {
+            Ok(())
+        })?;
+    }
+
+    if let Some(cache) = &cache {
+        cache.prune();
+    }
+
+    state.summary.elapsed = start.elapsed();
+    printer.print_report(if opt.summary { Some(&state.summary) } else { None })?;
+
+    if let Some(path) = &opt.write_baseline {
+        Baseline::write(path, &state.new_baseline)?;
+        if !opt.silent {
             printer.print_info(&format!(
-                "{} '{}'",
-                if pass { "pass" } else { "fail" },
+                "recorded {} failure(s) in baseline '{}'",
+                state.new_baseline.len(),
                 path.display()
             ))?;
         }
-        all_pass &= pass;
+    }
+
+    // Entries for files which weren't linted in this run can't be judged.
+    if let Some(baseline) = &state.baseline {
+        if !opt.silent {
+            for x in baseline.unmatched().filter(|x| state.paths.contains(&x.path)) {
+                printer.print_info(&format!(
+                    "baseline entry no longer occurs: {} '{}' in '{}'",
+                    x.rule, x.source, x.path
+                ))?;
+            }
+        }
+    }
+
+    // Warnings only affect the exit status when requested.
+    let max_warnings = if opt.deny_warnings { Some(0) } else { opt.max_warnings };
+    if let Some(max_warnings) = max_warnings {
+        if state.n_warnings > max_warnings {
+            all_pass = false;
+            if !opt.silent {
+                printer.print_warning(&format!(
+                    "{} warning(s) exceed the maximum of {}",
+                    state.n_warnings, max_warnings
+                ))?;
+            }
+        }
+    }
+
+    // Fixes are applied only after all files are processed, so that every
+    // failure is reported against the original text.
+    if opt.fix || opt.fix_dry_run {
+        fix_files(printer, opt, &state.fixes)?;
     }
 
     Ok(all_pass && not_obsolete)
 }
 
+fn collect_fixes(fixes: &mut BTreeMap<PathBuf, Vec<Fix>>, failed: &LintFailed) {
+    if !failed.fixes.is_empty() {
+        fixes
+            .entry(failed.path.clone())
+            .or_default()
+            .extend(failed.fixes.iter().cloned());
+    }
+}
+
 #[cfg_attr(tarpaulin, skip)]
-fn print_parser_error(
+fn fix_files(
     printer: &mut Printer,
-    error: SvParserError,
-    oneline: bool,
+    opt: &Opt,
+    fixes: &BTreeMap<PathBuf, Vec<Fix>>,
 ) -> Result<(), Error> {
-    match error {
-        SvParserError::Parse(Some((path, pos))) => {
-            printer.print_parse_error(&path, pos, oneline)?;
-        }
-        SvParserError::Preprocess(Some((path, pos))) => {
-            printer.print_preprocess_error(&path, pos, oneline)?;
-        }
-        SvParserError::Include { source } => match *source {
-            SvParserError::File { source: _, path } => {
-                printer.print_error(&format!("failed to include '{}'", path.display()))?;
-            }
-            SvParserError::DefineNotFound(define) => {
-                printer.print_error(&format!("definition not found for '{}'", define))?;
-            }
-            _ => {
-                printer.print_error(&format!("{}", source))?;
+    for (path, fixes) in fixes {
+        let mut file = File::open(path)
+            .with_context(|| format!("failed to open '{}'", path.to_string_lossy()))?;
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer)?;
+
+        // Fix locations are offsets into the decoded text, which only match
+        // offsets into the file when no decoding was necessary.
+        let text = match String::from_utf8(buffer) {
+            Ok(x) => x,
+            Err(_) => {
+                let msg = format!(
+                    "Fixes for '{}' are skipped as it is not valid UTF-8",
+                    path.to_string_lossy()
+                );
+                printer.print_warning(&msg)?;
+                continue;
             }
-        },
-        SvParserError::ReadUtf8(path) => {
-            printer.print_error(&format!("file '{}' is not valid UTF-8", path.display()))?;
-        }
-        SvParserError::DefineNoArgs(x) => {
-            printer.print_error(&format!("macro '{}' requires arguments", x))?;
-        }
-        SvParserError::DefineArgNotFound(x) => {
-            printer.print_error(&format!("macro argument '{}' is required", x))?;
-        }
-        SvParserError::DefineNotFound(x) => {
-            printer.print_error(&format!("macro '{}' is not defined", x))?;
+        };
+
+        let (fixed, n_fixes) = apply_fixes(&text, fixes);
+        if n_fixes == 0 {
+            continue;
         }
-        x => {
-            printer.print_error(&format!("{}", x))?;
+
+        if opt.fix_dry_run {
+            let path = path.to_string_lossy();
+            let diff = TextDiff::from_lines(&text, &fixed);
+            let msg = format!(
+                "{}",
+                diff.unified_diff()
+                    .header(&format!("a/{}", path), &format!("b/{}", path))
+            );
+            printer.print(&msg)?;
+        } else {
+            let mut f = OpenOptions::new()
+                .write(true)
+                .truncate(true)
+                .open(path)
+                .with_context(|| format!("failed to open '{}'", path.to_string_lossy()))?;
+            write!(f, "{}", fixed)
+                .with_context(|| format!("failed to write '{}'", path.to_string_lossy()))?;
+
+            if !opt.silent {
+                printer.print_info(&format!(
+                    "fixed {} failure(s) in '{}'",
+                    n_fixes,
+                    path.display()
+                ))?;
+            }
         }
     }
 
     Ok(())
 }
 
+// Everything which may be printed for one file, in order.
+enum FileOutput {
+    Failed(LintFailed),
+    ParseFailed(ParseFailed),
+    Text(String),
+}
+
+// Rule failures only affect `pass` after they're checked against the
+// baseline in `print_file_result`.
+struct FileResult {
+    pass: bool,
+    outputs: Vec<FileOutput>,
+    defines: Option<Defines>,
+    n_suppressed: usize,
+}
+
+// State accumulated over all files, in the order they're printed.
+#[derive(Default)]
+struct RunState {
+    n_warnings: usize,
+    fixes: BTreeMap<PathBuf, Vec<Fix>>,
+    fingerprinter: Fingerprinter,
+    baseline: Option<Baseline>,
+    new_baseline: Vec<Fingerprint>,
+    diff: Option<Diff>,
+    paths: HashSet<String>,
+    summary: Summary,
+}
+
 #[cfg_attr(tarpaulin, skip)]
-fn get_files_incdirs(
-    cli_files: Vec<PathBuf>,
-    cli_incdirs: Vec<PathBuf>
-) -> (Vec<PathBuf>, Vec<PathBuf>) {
-    let env_incdirs: Vec<PathBuf> = if let Ok(e) = env::var("SVLINT_INCDIRS") {
-        env::split_paths(&e).map(|x| PathBuf::from(x)).collect()
-    } else {
-        vec![]
+fn lint_file(
+    linter: &mut Linter,
+    opt: &Opt,
+    cache: Option<&Cache>,
+    path: &Path,
+    text: Option<&str>,
+    defines: &Defines,
+    incdirs: &[PathBuf],
+) -> Result<FileResult, Error> {
+    let mut ret = FileResult {
+        pass: true,
+        outputs: vec![],
+        defines: None,
+        n_suppressed: 0,
     };
 
-    let env_prefiles: Vec<PathBuf> = if let Ok(e) = env::var("SVLINT_PREFILES") {
-        env::split_paths(&e).map(|x| PathBuf::from(x)).collect()
+    if opt.preprocess_only {
+        let result = match text {
+            Some(x) => preprocess_str(x, path, defines, incdirs, opt.ignore_include, false, 0, 0),
+            None => preprocess(path, defines, incdirs, false, opt.ignore_include),
+        };
+        match result {
+            Ok((text, new_defines)) => {
+                ret.outputs.push(FileOutput::Text(text.text().to_string()));
+                ret.defines = Some(new_defines);
+            }
+            Err(x) => {
+                ret.outputs.push(FileOutput::ParseFailed(parse_failed(x)));
+                ret.pass = false;
+            }
+        }
     } else {
-        vec![]
-    };
 
-    let env_postfiles: Vec<PathBuf> = if let Ok(e) = env::var("SVLINT_POSTFILES") {
-        env::split_paths(&e).map(|x| PathBuf::from(x)).collect()
-    } else {
-        vec![]
-    };
+        // Signal beginning of file to all TextRules, which *may* be used
+        // by textrules to reset their internal state.
+        let _ = linter.textrules_check(TextRuleEvent::StartOfFile, path, &0);
 
-    let ret_files: Vec<PathBuf> = [env_prefiles, cli_files, env_postfiles].concat();
-    let ret_incdirs: Vec<PathBuf> = [env_incdirs, cli_incdirs].concat();
+        // Text given by the caller, i.e. from stdin or an editor, is linted in
+        // place of the file.
+        let text = match text {
+            Some(x) => x.to_string(),
+            None => read_source(path)?,
+        };
 
-    (ret_files, ret_incdirs)
-}
+        // Cached failures are replayed without parsing, but macros defined by
+        // the file must still be found for the following files.
+        let key = cache.and_then(|x| x.key(path, &text, defines, incdirs));
+        if let (Some(cache), Some(key)) = (cache, &key) {
+            if let Some(entry) = cache.get(path, key) {
+                let new_defines = if entry.defines_changed {
+                    preprocess_str(&text, path, defines, incdirs, opt.ignore_include, false, 0, 0)
+                        .ok()
+                        .map(|(_, x)| x)
+                } else {
+                    Some(defines.clone())
+                };
+                if let Some(new_defines) = new_defines {
+                    ret.outputs = entry.failed.into_iter().map(FileOutput::Failed).collect();
+                    ret.defines = Some(new_defines);
+                    ret.n_suppressed = entry.n_suppressed;
+                    return Ok(ret);
+                }
+            }
+        }
 
-#[cfg_attr(tarpaulin, skip)]
-fn search_config(printer: &mut Printer, config: &Path) -> Option<PathBuf> {
-    if let Ok(c) = env::var("SVLINT_CONFIG") {
-        let candidate = Path::new(&c);
-        if candidate.exists() {
-            return Some(candidate.to_path_buf());
-        } else {
-            let msg = format!(
-                "SVLINT_CONFIG=\"{}\" does not exist. Searching hierarchically.",
-                c,
-            );
+        let mut beg: usize = 0;
+
+        // Iterate over lines in the file, applying each textrule to each
+        // line in turn.
+        for line in text.split_inclusive('\n') {
+            let line_stripped = line.trim_end_matches(['\n', '\r']);
+
+            for failed in linter.textrules_check(TextRuleEvent::Line(line_stripped), path, &beg) {
+                ret.outputs.push(FileOutput::Failed(failed));
+            }
+            beg += line.len();
+        }
+
+        match parse_sv_str(text.as_str(), path, defines, incdirs, opt.ignore_include, false) {
+            Ok((syntax_tree, new_defines)) => {
+
+                // Iterate over nodes in the concrete syntax tree, applying
+                // each syntaxrule to each node in turn.
+                for node in syntax_tree.into_iter().event() {
+                    for failed in linter.syntaxrules_check(&syntax_tree, &node) {
+                        ret.outputs.push(FileOutput::Failed(failed));
+                    }
+                }
+                ret.defines = Some(new_defines);
+
+                if opt.dump_syntaxtree {
+                    ret.outputs.push(FileOutput::Text(format!("{:?}\n", &syntax_tree)));
+                }
+            }
+            Err(x) => {
+                ret.outputs.push(FileOutput::ParseFailed(parse_failed(x)));
+                ret.pass = false;
+            }
+        }
+
+        if opt.report_unused_suppressions {
+            for failed in linter.ctl_failures() {
+                ret.outputs.push(FileOutput::Failed(failed));
+            }
+        }
+        ret.n_suppressed = linter.n_suppressed();
+
+        // Files which fail to parse are always linted again, as they may
+        // depend on files which don't exist yet.
+        if let (Some(cache), Some(key), Some(new_defines)) = (cache, &key, &ret.defines) {
+            let failed = ret
+                .outputs
+                .iter()
+                .filter_map(|x| match x {
+                    FileOutput::Failed(x) => Some(x.clone()),
+                    _ => None,
+                })
+                .collect();
+            // The cache is only an optimisation, so failing to write it isn't
+            // an error.
+            let _ = cache.put(path, key, new_defines != defines, ret.n_suppressed, failed);
+        }
+    }
+
+    Ok(ret)
+}
+
+// Print each node matching the query, in every file, where the exit status is
+// like that of grep, i.e. passing only when something matches and every file
+// is parsed.
+#[cfg_attr(tarpaulin, skip)]
+fn run_query(
+    printer: &mut Printer,
+    opt: &Opt,
+    query: &Query,
+    files: &[PathBuf],
+    stdin: Option<&str>,
+    mut defines: Defines,
+    incdirs: &[PathBuf],
+) -> Result<bool, Error> {
+    if !matches!(opt.format, OutputFormat::Text | OutputFormat::Json | OutputFormat::Jsonl) {
+        return Err(anyhow!("--query supports only text, json, and jsonl formats"));
+    }
+
+    let mut sources: HashMap<PathBuf, String> = HashMap::new();
+    if let Some(s) = stdin {
+        sources.insert(opt.stdin_filename.clone(), s.to_string());
+    }
+    let mut records = Vec::new();
+    let mut all_parsed = true;
+    for path in files {
+        let text = match stdin.filter(|_| path == &opt.stdin_filename) {
+            Some(x) => x.to_string(),
+            None => read_source(path)?,
+        };
+        let (syntax_tree, new_defines) =
+            match parse_sv_str(&text, path, &defines, incdirs, opt.ignore_include, false) {
+                Ok(x) => x,
+                Err(x) => {
+                    let failed = parse_failed(x);
+                    if opt.format == OutputFormat::Text {
+                        printer.print_parse_failed(&failed, opt.oneline)?;
+                    } else {
+                        printer.print_warning(&failed.message)?;
+                    }
+                    all_parsed = false;
+                    continue;
+                }
+            };
+        if opt.compilation_unit {
+            defines = new_defines;
+        }
+
+        // Nodes are located by their first token, which may be in an
+        // included file.
+        let mut locate = |node: &sv_parser::RefNode| -> Result<Option<serde_json::Value>, Error> {
+            let (path, beg) = match unwrap_locate!(node.clone()).and_then(|x| syntax_tree.get_origin(x)) {
+                Some((path, beg)) => (path.clone(), beg),
+                None => return Ok(None),
+            };
+            if !sources.contains_key(&path) {
+                sources.insert(path.clone(), read_source(&path)?);
+            }
+            let (column, line) = Printer::get_pos(&sources[&path], beg).unwrap_or((1, 1));
+            let text = syntax_tree.get_str_trim(vec![node.clone()]).unwrap_or("");
+            Ok(Some(serde_json::json!({
+                "path": normalise_path(&path),
+                "line": line,
+                "column": column,
+                "kind": node.to_string(),
+                "text": text,
+            })))
+        };
+
+        for m in query.matches(&syntax_tree) {
+            let mut record = match locate(&m.node)? {
+                Some(x) => x,
+                None => continue,
+            };
+            let mut captures = serde_json::Map::new();
+            for (name, node) in &m.captures {
+                if let Some(x) = locate(node)? {
+                    captures.insert(name.clone(), x);
+                }
+            }
+            record["captures"] = serde_json::Value::Object(captures);
+            records.push(record);
+        }
+    }
+
+    match opt.format {
+        OutputFormat::Json => {
+            printer.println(&serde_json::to_string_pretty(&records)?)?;
+        }
+        OutputFormat::Jsonl => {
+            for record in &records {
+                printer.println(&record.to_string())?;
+            }
+        }
+        _ if !opt.silent => {
+            // Only the first line of each node's text is shown.
+            let first_line = |x: &serde_json::Value| x["text"].as_str().unwrap().lines().next().unwrap_or("").to_string();
+            for record in &records {
+                printer.println(&format!(
+                    "{}:{}:{}: {}: {}",
+                    record["path"].as_str().unwrap(),
+                    record["line"],
+                    record["column"],
+                    record["kind"].as_str().unwrap(),
+                    first_line(record),
+                ))?;
+                for (name, x) in record["captures"].as_object().unwrap() {
+                    printer.println(&format!(
+                        "    @{} {}:{}: {}",
+                        name,
+                        x["line"],
+                        x["column"],
+                        first_line(x),
+                    ))?;
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Ok(all_parsed && !records.is_empty())
+}
+
+#[cfg_attr(tarpaulin, skip)]
+fn print_file_result(
+    printer: &mut Printer,
+    opt: &Opt,
+    path: &Path,
+    result: FileResult,
+    state: &mut RunState,
+) -> Result<bool, Error> {
+    let mut pass = result.pass;
+    printer.begin_file(path);
+    state.paths.insert(baseline_path(path));
+    state.summary.files += 1;
+    state.summary.suppressed_by_comment += result.n_suppressed;
+
+    for output in &result.outputs {
+        match output {
+            FileOutput::Failed(failed) => {
+                if opt.write_baseline.is_some() {
+                    let fingerprint = state.fingerprinter.fingerprint(failed)?;
+                    state.new_baseline.push(fingerprint);
+                    state.summary.add_failed(failed);
+                    continue;
+                }
+                if let Some(baseline) = &mut state.baseline {
+                    let fingerprint = state.fingerprinter.fingerprint(failed)?;
+                    if baseline.suppress(&fingerprint) {
+                        state.summary.suppressed_by_baseline += 1;
+                        continue;
+                    }
+                }
+                // Files are still linted whole, so that syntaxrules have the
+                // full context of each change.
+                if let Some(diff) = &mut state.diff {
+                    if !diff.contains(failed)? {
+                        state.summary.suppressed_by_diff += 1;
+                        continue;
+                    }
+                }
+                state.summary.add_failed(failed);
+
+                // Only failures of rules with severity "error" cause a file to fail.
+                match failed.severity {
+                    Severity::Error => pass = false,
+                    Severity::Warning => state.n_warnings += 1,
+                    _ => {}
+                }
+                collect_fixes(&mut state.fixes, failed);
+                if !opt.silent {
+                    printer.print_failed(failed, opt.oneline, opt.github_actions)?;
+                }
+            }
+            FileOutput::ParseFailed(failed) => {
+                state.summary.parse_errors += 1;
+                printer.print_parse_failed(failed, opt.oneline)?;
+            }
+            FileOutput::Text(text) => {
+                printer.print(text)?;
+            }
+        }
+    }
+
+    if opt.verbose {
+        printer.print_info(&format!(
+            "{} '{}'",
+            if pass { "pass" } else { "fail" },
+            path.display()
+        ))?;
+    }
+
+    Ok(pass)
+}
+
+fn parse_failed(error: SvParserError) -> ParseFailed {
+    let (path, beg, kind, message) = match error {
+        SvParserError::Parse(Some((path, pos))) => {
+            (Some(path), Some(pos), "parse", String::from("parse error"))
+        }
+        SvParserError::Preprocess(Some((path, pos))) => {
+            (Some(path), Some(pos), "preprocess", String::from("preprocess error"))
+        }
+        SvParserError::Include { source } => match *source {
+            SvParserError::File { source: _, path } => {
+                let message = format!("failed to include '{}'", path.display());
+                (Some(path), None, "include", message)
+            }
+            SvParserError::DefineNotFound(define) => {
+                let message = format!("definition not found for '{}'", define);
+                (None, None, "include", message)
+            }
+            source => (None, None, "include", format!("{}", source)),
+        },
+        SvParserError::ReadUtf8(path) => {
+            let message = format!("file '{}' is not valid UTF-8", path.display());
+            (Some(path), None, "error", message)
+        }
+        SvParserError::DefineNoArgs(x) => {
+            (None, None, "preprocess", format!("macro '{}' requires arguments", x))
+        }
+        SvParserError::DefineArgNotFound(x) => {
+            (None, None, "preprocess", format!("macro argument '{}' is required", x))
+        }
+        SvParserError::DefineNotFound(x) => {
+            (None, None, "preprocess", format!("macro '{}' is not defined", x))
+        }
+        x => (None, None, "error", format!("{}", x)),
+    };
+
+    ParseFailed {
+        path,
+        beg,
+        kind: String::from(kind),
+        message,
+    }
+}
+
+#[cfg_attr(tarpaulin, skip)]
+fn get_files_incdirs(
+    cli_files: Vec<PathBuf>,
+    cli_incdirs: Vec<PathBuf>
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let env_incdirs: Vec<PathBuf> = if let Ok(e) = env::var("SVLINT_INCDIRS") {
+        env::split_paths(&e).map(|x| PathBuf::from(x)).collect()
+    } else {
+        vec![]
+    };
+
+    let env_prefiles: Vec<PathBuf> = if let Ok(e) = env::var("SVLINT_PREFILES") {
+        env::split_paths(&e).map(|x| PathBuf::from(x)).collect()
+    } else {
+        vec![]
+    };
+
+    let env_postfiles: Vec<PathBuf> = if let Ok(e) = env::var("SVLINT_POSTFILES") {
+        env::split_paths(&e).map(|x| PathBuf::from(x)).collect()
+    } else {
+        vec![]
+    };
+
+    let ret_files: Vec<PathBuf> = [env_prefiles, cli_files, env_postfiles].concat();
+    let ret_incdirs: Vec<PathBuf> = [env_incdirs, cli_incdirs].concat();
+
+    (ret_files, ret_incdirs)
+}
+
+#[cfg_attr(tarpaulin, skip)]
+fn search_config(printer: &mut Printer, config: &Path) -> Option<PathBuf> {
+    if let Ok(c) = env::var("SVLINT_CONFIG") {
+        let candidate = Path::new(&c);
+        if candidate.exists() {
+            return Some(candidate.to_path_buf());
+        } else {
+            let msg = format!(
+                "SVLINT_CONFIG=\"{}\" does not exist. Searching hierarchically.",
+                c,
+            );
             printer.print_warning(&msg).ok()?;
         }
     }
@@ -539,6 +1394,154 @@ fn dump_filelist(
     Ok(())
 }
 
+// Print a rule's documentation in the same form as MANUAL.md.
+fn explain(printer: &mut Printer, name: &str) -> Result<(), Error> {
+    let doc = match rule_doc(name) {
+        Some(x) => x,
+        None => {
+            let names = RULE_DOCS.iter().map(|x| x.name);
+            let msg = match did_you_mean(name, names) {
+                Some(x) => format!("unknown rule \"{}\", did you mean \"{}\"?", name, x),
+                None => format!("unknown rule \"{}\"", name),
+            };
+            return Err(anyhow!(msg));
+        }
+    };
+
+    let option = ConfigOption::default();
+    let (hint, reason) = if doc.ruletype == "text" {
+        let rule = Config::gen_all_textrules().into_iter().find(|x| x.name() == name).unwrap();
+        (rule.hint(&option), rule.reason())
+    } else {
+        let rule = Config::gen_all_syntaxrules().into_iter().find(|x| x.name() == name).unwrap();
+        (rule.hint(&option), rule.reason())
+    };
+
+    let title = if doc.ruletype == "text" { "Text Rule" } else { "Syntax Rule" };
+    let mut s = format!("## {}: `{}`\n\n", title, name);
+    s += &format!("### Hint\n\n{}\n\n", hint);
+    s += &format!("### Reason\n\n{}\n\n", reason);
+
+    let rulesets = rulesets_enabling(name);
+    let rulesets = if rulesets.is_empty() {
+        String::from("None")
+    } else {
+        rulesets.join(", ")
+    };
+    s += &format!("### Enabled by Rulesets\n\n{}\n\n", rulesets);
+
+    for (passfail, text) in [("Pass", doc.pass), ("Fail", doc.fail)] {
+        let testcases = testcases(text);
+        let n_testcases = testcases.len();
+        for (t, testcase) in testcases.iter().enumerate().map(|(i, x)| (i + 1, x)) {
+            s += &format!("### {} Example ({} of {})\n", passfail, t, n_testcases);
+            s += &format!("```systemverilog\n{}\n```\n\n", testcase);
+        }
+    }
+
+    s += &format!("### Explanation\n\n{}", doc.explanation);
+    printer.print(&s)?;
+    Ok(())
+}
+
+// Rules are listed with severities from the configuration before any
+// `[[overrides]]` are applied, as those depend on each file's path.
+fn list_rules(printer: &mut Printer, opt: &Opt, config: &Config) -> Result<(), Error> {
+    let mut linter = Linter::new(config.clone());
+    for plugin in &opt.plugins {
+        linter.load(plugin)?;
+    }
+
+    let mut severities = HashMap::new();
+    for (rule, severity) in config.gen_textrules_with_severity() {
+        severities.insert(rule.name(), severity);
+    }
+    for (rule, severity) in config.gen_syntaxrules_with_severity() {
+        severities.insert(rule.name(), severity);
+    }
+    let mut membership = ruleset_membership();
+
+    let mut rules = Vec::new();
+    for doc in RULE_DOCS {
+        let severity = severities.get(doc.name).copied().unwrap_or(Severity::Off);
+        rules.push(serde_json::json!({
+            "name": doc.name,
+            "type": doc.ruletype,
+            "category": rule_category(doc.name),
+            "severity": severity.as_str(),
+            "enabled": severity != Severity::Off,
+            "source": "builtin",
+            "options": doc.options,
+            "rulesets": membership.remove(doc.name).unwrap_or_default(),
+        }));
+    }
+    for rule in &config.custom_syntaxrules {
+        rules.push(serde_json::json!({
+            "name": rule.name,
+            "type": "syntax",
+            "category": rule_category(&rule.name),
+            "severity": rule.severity.as_str(),
+            "enabled": rule.severity != Severity::Off,
+            "source": "config",
+            "options": [],
+            "rulesets": [],
+        }));
+    }
+    // Plugins may only be enabled or disabled as a whole, and their options
+    // aren't known.
+    for (name, ruletype, path) in linter.plugin_rules() {
+        rules.push(serde_json::json!({
+            "name": name,
+            "type": ruletype,
+            "category": rule_category(&name),
+            "severity": Severity::Error.as_str(),
+            "enabled": true,
+            "source": path.to_string_lossy(),
+            "options": [],
+            "rulesets": [],
+        }));
+    }
+
+    match opt.format {
+        OutputFormat::Text => {
+            let width = rules.iter().map(|x| x["name"].as_str().unwrap().len()).max().unwrap_or(0);
+            let join = |x: &serde_json::Value| {
+                let x: Vec<&str> = x.as_array().unwrap().iter().map(|x| x.as_str().unwrap()).collect();
+                if x.is_empty() {
+                    String::from("-")
+                } else {
+                    x.join(",")
+                }
+            };
+            for rule in &rules {
+                printer.println(&format!(
+                    "{:width$}  {:6}  {:10}  {:7}  {}  options={}  rulesets={}",
+                    rule["name"].as_str().unwrap(),
+                    rule["type"].as_str().unwrap(),
+                    rule["category"].as_str().unwrap(),
+                    rule["severity"].as_str().unwrap(),
+                    rule["source"].as_str().unwrap(),
+                    join(&rule["options"]),
+                    join(&rule["rulesets"]),
+                    width = width,
+                ))?;
+            }
+        }
+        OutputFormat::Json => {
+            printer.println(&serde_json::to_string_pretty(&rules)?)?;
+        }
+        OutputFormat::Jsonl => {
+            for rule in &rules {
+                printer.println(&rule.to_string())?;
+            }
+        }
+        OutputFormat::Sarif | OutputFormat::Checkstyle | OutputFormat::Junit | OutputFormat::Gitlab => {
+            return Err(anyhow!("--list-rules supports only text, json, and jsonl formats"));
+        }
+    }
+    Ok(())
+}
+
 fn shell_completion<G: clap_complete::Generator>(gen: G, cmd: &mut clap::Command) {
     clap_complete::generate(gen, cmd, cmd.get_name().to_string(), &mut std::io::stdout());
 }
@@ -930,169 +1933,400 @@ mod tests {
         let opt = Opt::parse_from(args.iter());
     } // }}}
 
-    // NOTE: Testing clap's -h/--help interfers with `cargo test`.
-
     #[test]
     #[allow(unused_variables)]
-    fn cli_incdirs() {
+    fn cli_format() {
         // {{{
         let mut args = vec!["svlint"];
-        args.push("-I");
-        args.push("path/to/foo");
-        args.push("-I");
-        args.push("/path/to/bar");
+        args.push("--format=text");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
 
         let mut args = vec!["svlint"];
-        args.push("-Ipath/to/foo");
-        args.push("-I/path/to/bar");
+        args.push("--format");
+        args.push("sarif");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
 
         let mut args = vec!["svlint"];
-        args.push("--incdir");
-        args.push("path/to/foo");
-        args.push("--incdir");
-        args.push("/path/to/bar");
+        args.push("--format=json");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
 
-        // Aliases for backwards compatibility svlint v0.8.0 and earlier. //////
         let mut args = vec!["svlint"];
-        args.push("-i");
-        args.push("path/to/foo");
-        args.push("-i");
-        args.push("/path/to/bar");
+        args.push("--format=jsonl");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
 
         let mut args = vec!["svlint"];
-        args.push("-ipath/to/foo");
-        args.push("-i/path/to/bar");
+        args.push("--format=checkstyle");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
 
         let mut args = vec!["svlint"];
-        args.push("--include");
-        args.push("path/to/foo");
-        args.push("--include");
-        args.push("/path/to/bar");
+        args.push("--format=junit");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
 
-    } // }}}
-
-    #[test]
-    #[allow(unused_variables)]
-    fn cli_ignore_include() {
-        // {{{
         let mut args = vec!["svlint"];
-        args.push("--ignore-include");
+        args.push("--format=gitlab");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
     } // }}}
 
     #[test]
     #[allow(unused_variables)]
-    fn cli_plugins() {
+    fn cli_fix() {
         // {{{
         let mut args = vec!["svlint"];
-        args.push("-p");
-        args.push("path/to/libfoo.so"); // Linux
-        args.push("-p");
-        args.push("path/to/libbar.so");
+        args.push("--fix");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert!(opt.fix);
 
         let mut args = vec!["svlint"];
-        args.push("-p");
-        args.push("path/to/libfoo.dylib"); // MacOS
+        args.push("--fix-dry-run");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert!(opt.fix_dry_run);
 
         let mut args = vec!["svlint"];
-        args.push("-p");
-        args.push("path\\to\\foo.dll"); // Windows
+        args.push("--fix");
+        args.push("--fix-dry-run");
+        args.push("foo.sv");
+        let opt = Opt::try_parse_from(args.iter());
+        assert!(opt.is_err());
+    } // }}}
+
+    #[test]
+    #[allow(unused_variables)]
+    fn cli_jobs() {
+        // {{{
+        let mut args = vec!["svlint"];
+        args.push("-j");
+        args.push("4");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.jobs, 4);
 
         let mut args = vec!["svlint"];
-        args.push("-pfoo.so");
-        args.push("-pbar.so");
+        args.push("--jobs=0");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.jobs, 0);
 
         let mut args = vec!["svlint"];
-        args.push("--plugin");
-        args.push("foo.so");
+        args.push("-j8");
+        args.push("--compilation-unit");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert!(opt.compilation_unit);
     } // }}}
 
     #[test]
     #[allow(unused_variables)]
-    fn cli_silent() {
+    fn cli_warnings() {
         // {{{
         let mut args = vec!["svlint"];
-        args.push("-s");
+        args.push("--deny-warnings");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert!(opt.deny_warnings);
 
         let mut args = vec!["svlint"];
-        args.push("--silent");
+        args.push("--max-warnings");
+        args.push("10");
         args.push("foo.sv");
         let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.max_warnings, Some(10));
     } // }}}
 
     #[test]
-    #[allow(unused_variables)]
-    fn cli_verbose() {
+    fn cli_baseline() {
         // {{{
-        let mut args = vec!["svlint"];
-        args.push("-v");
-        args.push("foo.sv");
+        let args = ["svlint", "--baseline", "baseline.json", "foo.sv"];
         let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.baseline, Some(PathBuf::from("baseline.json")));
 
-        let mut args = vec!["svlint"];
-        args.push("--verbose");
-        args.push("foo.sv");
+        let args = ["svlint", "--write-baseline", "baseline.json", "foo.sv"];
         let opt = Opt::parse_from(args.iter());
-    } // }}}
+        assert_eq!(opt.write_baseline, Some(PathBuf::from("baseline.json")));
 
-    // NOTE: Testing clap's -V/--version interfers with `cargo test`.
+        let args = ["svlint", "--baseline=a.json", "--write-baseline=b.json", "foo.sv"];
+        assert!(Opt::try_parse_from(args.iter()).is_err());
+    } // }}}
 
     #[test]
-    fn dump_filelist_1() {
+    fn cli_diff() {
         // {{{
-        let config: Config = toml::from_str("").unwrap();
+        let args = ["svlint", "--diff-file", "a.patch", "foo.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.diff_file, Some(PathBuf::from("a.patch")));
 
-        // Files, not filelist.
-        let mut args = vec!["svlint"];
-        args.push("--dump-filelist=yaml");
-        args.push("foo/bar/one.sv");
-        args.push("foo/bar/two.sv");
+        let args = ["svlint", "--diff-base", "origin/main", "foo.sv"];
         let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.diff_base, Some(String::from("origin/main")));
 
-        let mut printer = Printer::new(true);
-        let ret = run_opt_config(&mut printer, &opt, config.clone());
-        assert_eq!(ret.unwrap(), true);
+        let args = ["svlint", "--diff-file=a.patch", "--diff-base=main", "foo.sv"];
+        assert!(Opt::try_parse_from(args.iter()).is_err());
 
-        let stdout = printer.read_to_string().unwrap();
-        assert_eq!(stdout, expected_contents("dump_filelist_1"));
+        let args = ["svlint", "--diff-base=main", "--write-baseline=b.json", "foo.sv"];
+        assert!(Opt::try_parse_from(args.iter()).is_err());
     } // }}}
 
     #[test]
-    fn dump_filelist_2() {
+    fn cli_report_unused_suppressions() {
         // {{{
-        let config: Config = toml::from_str("").unwrap();
-
-        // Single flat filelist.
-        let mut args = vec!["svlint"];
-        args.push("--dump-filelist=yaml");
-        args.push("--filelist");
-        let f_1 = resources_path("child1.fl");
-        args.push(&f_1);
+        let args = ["svlint", "--report-unused-suppressions", "foo.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.report_unused_suppressions);
+    } // }}}
+
+    #[test]
+    fn cli_summary() {
+        // {{{
+        let args = ["svlint", "--summary", "foo.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.summary);
+
+        let args = ["svlint", "--stats", "foo.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.summary);
+    } // }}}
+
+    #[test]
+    fn cli_explain() {
+        // {{{
+        let args = ["svlint", "--explain", "eventlist_or"];
+        let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.explain, Some(String::from("eventlist_or")));
+    } // }}}
+
+    #[test]
+    fn cli_list_rules() {
+        // {{{
+        let args = ["svlint", "--list-rules"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.list_rules);
+    } // }}}
+
+    #[test]
+    fn cli_cache() {
+        // {{{
+        let args = ["svlint", "a.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(!opt.cache);
+        assert_eq!(opt.cache_dir, None);
+
+        let args = ["svlint", "--cache", "--cache-dir", "/tmp/x", "a.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.cache);
+        assert_eq!(opt.cache_dir, Some(PathBuf::from("/tmp/x")));
+    } // }}}
+
+    #[test]
+    fn cli_watch() {
+        // {{{
+        let args = ["svlint", "--watch", "a.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.watch);
+    } // }}}
+
+    #[test]
+    fn cli_lsp() {
+        // {{{
+        let args = ["svlint", "--lsp"];
+        let opt = Opt::parse_from(args.iter());
+        assert!(opt.lsp);
+    } // }}}
+
+    #[test]
+    fn cli_stdin() {
+        // {{{
+        let args = ["svlint", "-"];
+        let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.files, vec![PathBuf::from("-")]);
+        assert_eq!(opt.stdin_filename, PathBuf::from("<stdin>"));
+
+        let args = ["svlint", "--stdin-filename", "foo/bar.sv", "-"];
+        let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.stdin_filename, PathBuf::from("foo/bar.sv"));
+    } // }}}
+
+    #[test]
+    fn cli_query() {
+        // {{{
+        let args = ["svlint", "--query", "AlwaysConstruct LoopStatement", "a.sv"];
+        let opt = Opt::parse_from(args.iter());
+        assert_eq!(opt.query.unwrap().to_string(), "AlwaysConstruct LoopStatement");
+
+        let args = ["svlint", "--query", "AlwaysConstruct >", "a.sv"];
+        assert!(Opt::try_parse_from(args.iter()).is_err());
+    } // }}}
+
+    // NOTE: Testing clap's -h/--help interfers with `cargo test`.
+
+    #[test]
+    #[allow(unused_variables)]
+    fn cli_incdirs() {
+        // {{{
+        let mut args = vec!["svlint"];
+        args.push("-I");
+        args.push("path/to/foo");
+        args.push("-I");
+        args.push("/path/to/bar");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("-Ipath/to/foo");
+        args.push("-I/path/to/bar");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("--incdir");
+        args.push("path/to/foo");
+        args.push("--incdir");
+        args.push("/path/to/bar");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        // Aliases for backwards compatibility svlint v0.8.0 and earlier. //////
+        let mut args = vec!["svlint"];
+        args.push("-i");
+        args.push("path/to/foo");
+        args.push("-i");
+        args.push("/path/to/bar");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("-ipath/to/foo");
+        args.push("-i/path/to/bar");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("--include");
+        args.push("path/to/foo");
+        args.push("--include");
+        args.push("/path/to/bar");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+    } // }}}
+
+    #[test]
+    #[allow(unused_variables)]
+    fn cli_ignore_include() {
+        // {{{
+        let mut args = vec!["svlint"];
+        args.push("--ignore-include");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+    } // }}}
+
+    #[test]
+    #[allow(unused_variables)]
+    fn cli_plugins() {
+        // {{{
+        let mut args = vec!["svlint"];
+        args.push("-p");
+        args.push("path/to/libfoo.so"); // Linux
+        args.push("-p");
+        args.push("path/to/libbar.so");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("-p");
+        args.push("path/to/libfoo.dylib"); // MacOS
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("-p");
+        args.push("path\\to\\foo.dll"); // Windows
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("-pfoo.so");
+        args.push("-pbar.so");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("--plugin");
+        args.push("foo.so");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+    } // }}}
+
+    #[test]
+    #[allow(unused_variables)]
+    fn cli_silent() {
+        // {{{
+        let mut args = vec!["svlint"];
+        args.push("-s");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("--silent");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+    } // }}}
+
+    #[test]
+    #[allow(unused_variables)]
+    fn cli_verbose() {
+        // {{{
+        let mut args = vec!["svlint"];
+        args.push("-v");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut args = vec!["svlint"];
+        args.push("--verbose");
+        args.push("foo.sv");
+        let opt = Opt::parse_from(args.iter());
+    } // }}}
+
+    // NOTE: Testing clap's -V/--version interfers with `cargo test`.
+
+    #[test]
+    fn dump_filelist_1() {
+        // {{{
+        let config: Config = toml::from_str("").unwrap();
+
+        // Files, not filelist.
+        let mut args = vec!["svlint"];
+        args.push("--dump-filelist=yaml");
+        args.push("foo/bar/one.sv");
+        args.push("foo/bar/two.sv");
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert_eq!(ret.unwrap(), true);
+
+        let stdout = printer.read_to_string().unwrap();
+        assert_eq!(stdout, expected_contents("dump_filelist_1"));
+    } // }}}
+
+    #[test]
+    fn dump_filelist_2() {
+        // {{{
+        let config: Config = toml::from_str("").unwrap();
+
+        // Single flat filelist.
+        let mut args = vec!["svlint"];
+        args.push("--dump-filelist=yaml");
+        args.push("--filelist");
+        let f_1 = resources_path("child1.fl");
+        args.push(&f_1);
         let opt = Opt::parse_from(args.iter());
 
         let mut printer = Printer::new(true);
@@ -1232,6 +2466,1466 @@ mod tests {
         assert_eq!(stdout, expected_contents("dump_filelist_8"));
     } // }}}
 
+    #[test]
+    fn format_sarif() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--format=sarif");
+        let f_1 = resources_path("eventlist_or.sv");
+        args.push(&f_1);
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let log: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "eventlist_or");
+        assert_eq!(run["results"].as_array().unwrap().len(), 1);
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "eventlist_or");
+        let region = &result["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 6);
+        assert_eq!(region["startColumn"], 15);
+    } // }}}
+
+    #[test]
+    fn format_sarif_missing_include() {
+        // {{{
+        let config: Config = toml::from_str("").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_sarif_include_{}.sv", process::id()));
+        std::fs::write(&f_1, "`include \"nope.svh\"\nmodule M;\nendmodule\n").unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--format=sarif");
+        args.push(f_1.to_str().unwrap());
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        std::fs::remove_file(&f_1).unwrap();
+        assert!(!ret.unwrap());
+
+        // The missing include is reported without a region.
+        let stdout = printer.read_to_string().unwrap();
+        let log: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        let invocation = &log["runs"][0]["invocations"][0];
+        assert_eq!(invocation["executionSuccessful"], false);
+        let notification = &invocation["toolExecutionNotifications"][0];
+        assert_eq!(notification["level"], "error");
+        assert!(notification["locations"][0]["physicalLocation"]["region"].is_null());
+    } // }}}
+
+    #[test]
+    fn format_json() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--format=json");
+        let f_1 = resources_path("eventlist_or.sv");
+        args.push(&f_1);
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        assert_eq!(report["errors"].as_array().unwrap().len(), 0);
+        assert_eq!(report["failures"].as_array().unwrap().len(), 1);
+
+        let failure = &report["failures"][0];
+        assert_eq!(failure["type"], "failure");
+        assert_eq!(failure["path"], f_1.as_str());
+        assert_eq!(failure["rule"], "eventlist_or");
+        assert_eq!(failure["line"], 6);
+        assert_eq!(failure["column"], 15);
+        assert_eq!(failure["source"], "  always_ff @(posedge i_clk or posedge i_arst)");
+
+        // Positions are found in the decoded text of a GBK-encoded file.
+        let f_2 = env::temp_dir().join(format!("svlint_format_json_gbk_{}.sv", process::id()));
+        let f_2 = f_2.to_str().unwrap();
+        let mut bytes = b"// ".to_vec();
+        for _ in 0..4 {
+            bytes.extend([0xd6, 0xd0, 0xce, 0xc4, 0xd7, 0xa2, 0xca, 0xcd]);
+        }
+        bytes.push(b'\n');
+        bytes.extend(std::fs::read(&f_1).unwrap());
+        std::fs::write(f_2, &bytes).unwrap();
+
+        for format in ["--format=json", "--format=jsonl"] {
+            let args = ["svlint", format, f_2];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(!ret.unwrap());
+
+            let stdout = printer.read_to_string().unwrap();
+            let failure: serde_json::Value = match format {
+                "--format=json" => serde_json::from_str::<serde_json::Value>(&stdout).unwrap()["failures"][0].clone(),
+                _ => serde_json::from_str(stdout.lines().next().unwrap()).unwrap(),
+            };
+            assert_eq!(failure["line"], 7);
+            assert_eq!(failure["column"], 15);
+            assert_eq!(failure["source"], "  always_ff @(posedge i_clk or posedge i_arst)");
+        }
+        std::fs::remove_file(f_2).unwrap();
+    } // }}}
+
+    #[test]
+    fn format_jsonl() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--format=jsonl");
+        let f_1 = resources_path("eventlist_or.sv");
+        args.push(&f_1);
+        let f_2 = resources_path("parse_error.sv");
+        args.push(&f_2);
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let records: Vec<serde_json::Value> = stdout
+            .lines()
+            .map(|x| serde_json::from_str(x).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["type"], "failure");
+        assert_eq!(records[0]["rule"], "eventlist_or");
+        assert_eq!(records[1]["type"], "error");
+        assert_eq!(records[1]["kind"], "parse");
+        assert_eq!(records[1]["path"], f_2.as_str());
+        assert_eq!(records[1]["line"], 2);
+    } // }}}
+
+    #[test]
+    fn format_checkstyle() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = \"warning\"").unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--format=checkstyle");
+        let f_1 = resources_path("eventlist_or.sv");
+        args.push(&f_1);
+        let f_2 = resources_path("parse_error.sv");
+        args.push(&f_2);
+        let f_3 = resources_path("must_pass_all_rulesets.sv");
+        args.push(&f_3);
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+        assert_eq!(lines[1], "<checkstyle version=\"4.3\">");
+        assert_eq!(lines[2], format!("  <file name=\"{}\">", f_1));
+        assert_eq!(
+            lines[3],
+            "    <error line=\"6\" column=\"15\" severity=\"warning\" \
+             message=\"Use comma event expression separator instead of `or`.\" source=\"eventlist_or\"/>"
+        );
+        assert_eq!(lines[4], "  </file>");
+        assert_eq!(lines[5], format!("  <file name=\"{}\">", f_2));
+        assert_eq!(
+            lines[6],
+            "    <error line=\"2\" column=\"10\" severity=\"error\" message=\"parse error\" source=\"parse\"/>"
+        );
+        assert_eq!(lines[7], "  </file>");
+        assert_eq!(lines[8], format!("  <file name=\"{}\"/>", f_3));
+        assert_eq!(lines[9], "</checkstyle>");
+    } // }}}
+
+    #[test]
+    fn format_junit() {
+        // {{{
+        let s = "[syntaxrules]\neventlist_or = true\nkeyword_forbidden_always_ff = \"warning\"";
+        let config: Config = toml::from_str(s).unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--format=junit");
+        let f_1 = resources_path("eventlist_or.sv");
+        args.push(&f_1);
+        let f_2 = resources_path("parse_error.sv");
+        args.push(&f_2);
+        let f_3 = resources_path("must_pass_all_rulesets.sv");
+        args.push(&f_3);
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        assert!(stdout.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(stdout.contains("<testsuite name=\"svlint\" tests=\"3\" failures=\"1\" errors=\"1\">\n"));
+        assert!(stdout.contains(&format!(
+            "<testcase name=\"{}\" classname=\"svlint\">\n      \
+             <failure message=\"1 rule failure(s)\" type=\"svlint\">\
+             {}:6:15: eventlist_or: Use comma event expression separator instead of `or`.</failure>\n      \
+             <system-out>warning: {}:6:3: keyword_forbidden_always_ff: ",
+            f_1, f_1, f_1
+        )));
+        assert!(stdout.contains(&format!(
+            "<error message=\"parse error\" type=\"parse\">{}:2:10: parse error</error>",
+            f_2
+        )));
+        assert!(stdout.contains(&format!("<testcase name=\"{}\" classname=\"svlint\"/>", f_3)));
+        assert!(stdout.ends_with("</testsuites>\n"));
+    } // }}}
+
+    #[test]
+    fn format_gitlab() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = \"warning\"").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_format_gitlab_{}.sv", process::id()));
+        let f_1 = f_1.to_str().unwrap();
+        let f_2 = resources_path("parse_error.sv");
+        let text = std::fs::read_to_string(resources_path("eventlist_or.sv")).unwrap();
+
+        let mut issues = vec![];
+        for text in [text.clone(), format!("\n\n{}", text)] {
+            std::fs::write(f_1, &text).unwrap();
+            let args = ["svlint", "--format=gitlab", f_1, &f_2];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(!ret.unwrap());
+
+            let stdout = printer.read_to_string().unwrap();
+            let report: Vec<serde_json::Value> = serde_json::from_str(&stdout).unwrap();
+            issues.push(report);
+        }
+        let _ = std::fs::remove_file(f_1);
+
+        assert_eq!(issues[0].len(), 2);
+        let issue = &issues[0][0];
+        assert_eq!(issue["type"], "issue");
+        assert_eq!(issue["check_name"], "eventlist_or");
+        assert_eq!(issue["description"], "Use comma event expression separator instead of `or`.");
+        assert_eq!(issue["severity"], "minor");
+        assert_eq!(issue["location"]["path"], baseline_path(Path::new(f_1)));
+        assert_eq!(issue["location"]["lines"]["begin"], 6);
+        let issue = &issues[0][1];
+        assert_eq!(issue["check_name"], "parse");
+        assert_eq!(issue["severity"], "critical");
+        assert_eq!(issue["location"]["lines"]["begin"], 2);
+
+        // Fingerprints don't depend on line numbers.
+        assert_eq!(issues[1][0]["location"]["lines"]["begin"], 8);
+        assert_eq!(issues[0][0]["fingerprint"], issues[1][0]["fingerprint"]);
+        assert_ne!(issues[0][0]["fingerprint"], issues[0][1]["fingerprint"]);
+    } // }}}
+
+    #[test]
+    fn fix_dry_run() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--silent");
+        args.push("--fix-dry-run");
+        let f_1 = resources_path("eventlist_or.sv");
+        args.push(&f_1);
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        assert!(stdout.contains("-  always_ff @(posedge i_clk or posedge i_arst)\n"));
+        assert!(stdout.contains("+  always_ff @(posedge i_clk, posedge i_arst)\n"));
+    } // }}}
+
+    #[test]
+    fn fix() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_fix_{}.sv", process::id()));
+        std::fs::copy(resources_path("eventlist_or.sv"), &f_1).unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--silent");
+        args.push("--fix");
+        args.push(f_1.to_str().unwrap());
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        // Once fixed, the file passes.
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        let text = std::fs::read_to_string(&f_1).unwrap();
+        std::fs::remove_file(&f_1).unwrap();
+        assert!(text.contains("  always_ff @(posedge i_clk, posedge i_arst)\n"));
+    } // }}}
+
+    #[test]
+    fn fix_outside_strings() {
+        // {{{
+        let config: Config = toml::from_str("[textrules]\nstyle_semicolon = true").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_fix_outside_strings_{}.sv", process::id()));
+        let text = "module M;\n  initial $display(\"done ;\") ;\n  /* a ;\n  b ; */ assign a = b ;\nendmodule\n";
+        std::fs::write(&f_1, text).unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--silent");
+        args.push("--fix");
+        args.push(f_1.to_str().unwrap());
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let text = std::fs::read_to_string(&f_1).unwrap();
+        std::fs::remove_file(&f_1).unwrap();
+        assert!(text.contains("  initial $display(\"done ;\");\n"));
+        assert!(text.contains("  /* a ;\n  b ; */ assign a = b;\n"));
+    } // }}}
+
+    #[test]
+    fn fix_incdec_side_effects() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\noperator_incdec = true").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_fix_incdec_{}.sv", process::id()));
+        let text = "module M;\n  always @(posedge clk) a[i]++;\n  always @(posedge clk) b[f(i)]++;\nendmodule\n";
+        std::fs::write(&f_1, text).unwrap();
+
+        let mut args = vec!["svlint"];
+        args.push("--silent");
+        args.push("--fix");
+        args.push(f_1.to_str().unwrap());
+        let opt = Opt::parse_from(args.iter());
+
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        // Only an lvalue without side effects may be written twice.
+        let text = std::fs::read_to_string(&f_1).unwrap();
+        std::fs::remove_file(&f_1).unwrap();
+        assert!(text.contains("  always @(posedge clk) a[i] = a[i] + 1;\n"));
+        assert!(text.contains("  always @(posedge clk) b[f(i)]++;\n"));
+    } // }}}
+
+    #[test]
+    fn severity_config() {
+        // {{{
+        let s = "[syntaxrules]\neventlist_or = \"warning\"\ncase_default = true\n";
+        let config: Config = toml::from_str(s).unwrap();
+        assert_eq!(config.syntaxrules.eventlist_or, Severity::Warning);
+        assert_eq!(config.syntaxrules.case_default, Severity::Error);
+        assert_eq!(config.syntaxrules.multiline_if_begin, Severity::Off);
+
+        // Boolean values are kept where possible.
+        let s = toml::to_string(&config).unwrap();
+        assert!(s.contains("eventlist_or = \"warning\"\n"));
+        assert!(s.contains("case_default = true\n"));
+
+        let s = "[syntaxrules]\neventlist_or = \"warn\"\n";
+        let config: Result<Config, _> = toml::from_str(s);
+        assert!(config.is_err());
+    } // }}}
+
+    #[test]
+    fn severity_exit() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = \"warning\"").unwrap();
+        let f_1 = resources_path("eventlist_or.sv");
+
+        // Warnings don't cause failure by default.
+        let args = ["svlint", "--oneline", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert!(stdout.starts_with("Warning\t"));
+
+        let args = ["svlint", "--silent", "--deny-warnings", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let args = ["svlint", "--silent", "--max-warnings=1", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        let args = ["svlint", "--silent", "--max-warnings=0", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let args = ["svlint", "--format=jsonl", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        let record: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+        assert_eq!(record["severity"], "warning");
+    } // }}}
+
+    #[test]
+    fn baseline() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_baseline_{}.sv", process::id()));
+        let f_1 = f_1.to_str().unwrap();
+        let f_baseline = env::temp_dir().join(format!("svlint_baseline_{}.json", process::id()));
+        let f_baseline = f_baseline.to_str().unwrap();
+        let text = std::fs::read_to_string(resources_path("eventlist_or.sv")).unwrap();
+        std::fs::write(f_1, &text).unwrap();
+
+        // Existing failures are recorded without failing.
+        let args = ["svlint", "--silent", "--write-baseline", f_baseline, f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        // Recorded failures are suppressed, even when moved to another line.
+        std::fs::write(f_1, format!("\n\n{}", text)).unwrap();
+        let args = ["svlint", "--baseline", f_baseline, f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        assert_eq!(printer.read_to_string().unwrap(), "");
+
+        // New failures are reported, and fixed failures are noted.
+        let text_new = text.replace("posedge i_arst", "negedge i_arst");
+        std::fs::write(f_1, &text_new).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert!(stdout.contains("Fail"));
+        assert!(stdout.contains("baseline entry no longer occurs: eventlist_or"));
+
+        // Paths are recorded relative to the current directory, so failures
+        // match however their files are given.
+        let f_2 = resources_path("eventlist_or.sv");
+        let args = ["svlint", "--silent", "--write-baseline", f_baseline, &f_2];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        let baseline = std::fs::read_to_string(f_baseline).unwrap();
+        assert!(baseline.contains("\"path\": \"testcases/application/resources/eventlist_or.sv\""));
+
+        let f_2 = "./testcases/application/resources/eventlist_or.sv";
+        let args = ["svlint", "--baseline", f_baseline, f_2];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        assert_eq!(printer.read_to_string().unwrap(), "");
+
+        std::fs::remove_file(f_1).unwrap();
+        std::fs::remove_file(f_baseline).unwrap();
+    } // }}}
+
+    #[test]
+    fn baseline_gbk() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_baseline_gbk_{}.sv", process::id()));
+        let f_1 = f_1.to_str().unwrap();
+        let f_baseline = env::temp_dir().join(format!("svlint_baseline_gbk_{}.json", process::id()));
+        let f_baseline = f_baseline.to_str().unwrap();
+
+        // A GBK-encoded comment precedes the failure, so offsets into the
+        // decoded text differ from those into the file.
+        let mut bytes = b"// ".to_vec();
+        for _ in 0..4 {
+            bytes.extend([0xd6, 0xd0, 0xce, 0xc4, 0xd7, 0xa2, 0xca, 0xcd]);
+        }
+        bytes.push(b'\n');
+        bytes.extend(std::fs::read(resources_path("eventlist_or.sv")).unwrap());
+        std::fs::write(f_1, &bytes).unwrap();
+
+        let args = ["svlint", "--silent", "--write-baseline", f_baseline, f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        let baseline = std::fs::read_to_string(f_baseline).unwrap();
+        std::fs::remove_file(f_1).unwrap();
+        std::fs::remove_file(f_baseline).unwrap();
+        assert!(baseline.contains("always_ff @(posedge i_clk or posedge i_arst)"));
+    } // }}}
+
+    #[test]
+    fn diff() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true").unwrap();
+
+        let f_1 = env::temp_dir().join(format!("svlint_diff_{}.sv", process::id()));
+        let f_1 = f_1.to_str().unwrap();
+        let f_patch = env::temp_dir().join(format!("svlint_diff_{}.patch", process::id()));
+        let f_patch = f_patch.to_str().unwrap();
+        let text = "module M;\n  always @(a or b) q1 <= d;\n  always @(c or d) q2 <= d;\nendmodule\n";
+        std::fs::write(f_1, text).unwrap();
+
+        // Only the failure on the added line is reported.
+        let patch = format!(
+            "--- a/{0}\n+++ {0}\n@@ -1,3 +1,4 @@\n module M;\n   always @(a or b) q1 <= d;\n+  always @(c or d) q2 <= d;\n endmodule\n",
+            f_1
+        );
+        std::fs::write(f_patch, &patch).unwrap();
+        let args = ["svlint", "--oneline", "--summary", "--diff-file", f_patch, f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert!(!stdout.contains(":2:"));
+        assert!(stdout.contains(":3:"));
+        assert!(stdout.contains("0 by baseline, 1 outside diff)\n"));
+
+        // Files which aren't in the diff have no failures reported.
+        std::fs::write(f_patch, patch.replace("+++ ", "+++ other/")).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        // Removed lines don't cause failures to be reported.
+        let patch = format!("--- a/{0}\n+++ b/{0}\n@@ -2,1 +1,0 @@\n-  wire w;\n", f_1);
+        std::fs::write(f_patch, &patch).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        std::fs::write(f_patch, "--- a/x.sv\n+++ b/x.sv\n@@ -1 +1 @@\n+x\n").unwrap();
+        let mut printer = Printer::new(true);
+        assert!(run_opt_config(&mut printer, &opt, config.clone()).is_err());
+
+        // Lines are found in the decoded text, where a GBK-encoded comment is
+        // longer than in the file.
+        let mut bytes = b"// ".to_vec();
+        for _ in 0..8 {
+            bytes.extend([0xd6, 0xd0, 0xce, 0xc4, 0xd7, 0xa2, 0xca, 0xcd]);
+        }
+        bytes.extend(b"\nmodule M;\n  always @(a or b) q1 <= d;\nendmodule\n");
+        std::fs::write(f_1, &bytes).unwrap();
+        let patch = format!("--- a/{0}\n+++ b/{0}\n@@ -2,0 +3 @@\n+  always @(a or b) q1 <= d;\n", f_1);
+        std::fs::write(f_patch, &patch).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+        assert!(printer.read_to_string().unwrap().contains("0 by baseline, 0 outside diff)\n"));
+
+        // Paths in the diff, relative to the current directory, match files
+        // given by absolute paths or through `../`.
+        let f_2 = resources_path("eventlist_or.sv");
+        let patch = "--- a/testcases/application/resources/eventlist_or.sv\n\
+                     +++ b/testcases/application/resources/eventlist_or.sv\n\
+                     @@ -5,0 +6 @@\n+  always_ff @(posedge i_clk or posedge i_arst)\n";
+        std::fs::write(f_patch, patch).unwrap();
+        for f_2 in [f_2.as_str(), "testcases/../testcases/application/resources/eventlist_or.sv"] {
+            let args = ["svlint", "--oneline", "--diff-file", f_patch, f_2];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(!ret.unwrap());
+            assert!(printer.read_to_string().unwrap().contains(":6:15"));
+        }
+
+        let args = ["svlint", "--diff-base", "svlint-no-such-revision", f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        assert!(run_opt_config(&mut printer, &opt, config.clone()).is_err());
+
+        std::fs::remove_file(f_1).unwrap();
+        std::fs::remove_file(f_patch).unwrap();
+    } // }}}
+
+    #[test]
+    fn report_unused_suppressions() {
+        // {{{
+        let s = "[textrules]\nstyle_textwidth = true\n[syntaxrules]\neventlist_or = true\ncase_default = true\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let f_1 = resources_path("suppressions.sv");
+
+        // Control comments are silently ignored by default.
+        let args = ["svlint", "--oneline", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        assert_eq!(printer.read_to_string().unwrap(), "");
+
+        let args = ["svlint", "--oneline", "--report-unused-suppressions", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains(":2:3\t"));
+        assert!(lines[0].contains("did you mean `eventlist_or`?"));
+        assert!(lines[1].contains(":6:3\t"));
+        assert!(lines[1].contains("Remove `case_default`"));
+        assert!(lines[2].contains(":9:22\t"));
+        assert!(lines[2].contains("Remove `style_textwidth`"));
+    } // }}}
+
+    #[test]
+    fn suppressions_off_all() {
+        // {{{
+        let config: Config = toml::from_str("[syntaxrules]\neventlist_or = true\n").unwrap();
+        let f_1 = env::temp_dir().join(format!("svlint_off_all_{}.sv", process::id()));
+        let f_1 = f_1.to_str().unwrap();
+        std::fs::write(
+            f_1,
+            "module M;\n\
+             /* svlint off all */\n  always @(a or b) q1 <= d;\n/* svlint on all */\n\
+             /* svlint off eventlist_or */\n  always @(c or d) q2 <= d;\n/* svlint on eventlist_or */\n\
+             endmodule\n",
+        )
+        .unwrap();
+
+        // Rules disabled by `all` are only checked to report unused
+        // suppressions, so only then are their failures counted.
+        let run = |extra: &[&str]| {
+            let mut args = vec!["svlint", "--oneline", "--summary"];
+            args.extend(extra);
+            args.push(f_1);
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(ret.unwrap());
+            printer.read_to_string().unwrap()
+        };
+        let stdout = run(&[]);
+        assert!(stdout.contains("  suppressed:   1 (1 by control comments, "));
+        let stdout = run(&["--report-unused-suppressions"]);
+        assert!(stdout.starts_with("Summary\n"));
+        assert!(stdout.contains("  suppressed:   2 (2 by control comments, "));
+
+        std::fs::remove_file(f_1).unwrap();
+    } // }}}
+
+    #[test]
+    fn summary() {
+        // {{{
+        let s = "[syntaxrules]\neventlist_or = \"warning\"\nkeyword_forbidden_always_comb = true\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let f_1 = resources_path("eventlist_or.sv");
+        let f_2 = resources_path("parse_error.sv");
+        let f_3 = resources_path("suppressions.sv");
+
+        let args = ["svlint", "--oneline", "--summary", &f_1, &f_2, &f_3];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let summary = &stdout[stdout.find("Summary\n").unwrap()..];
+        assert!(summary.contains("  files linted: 3\n"));
+        assert!(summary.contains("  failures:     3 (2 error, 1 warning, 0 info)\n"));
+        assert!(summary.contains("  parse errors: 1\n"));
+        assert!(summary.contains("  suppressed:   1 (1 by control comments, 0 by baseline, 0 outside diff)\n"));
+        assert!(Regex::new(r"  elapsed time: [0-9]+\.[0-9]{2}s\n").unwrap().is_match(summary));
+        assert!(summary.contains("Failures by rule\n       2 keyword_forbidden_always_comb\n       1 eventlist_or\n"));
+        assert!(summary.contains(&format!("       2 {}\n       1 {}\n", f_3, f_1)));
+        assert!(summary.contains("Failures by directory\n       3 "));
+
+        let args = ["svlint", "--format", "json", "--summary", &f_1, &f_2, &f_3];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+        let summary = &report["summary"];
+        assert_eq!(summary["files"], 3);
+        assert_eq!(summary["failures"], 3);
+        assert_eq!(summary["errors"], 2);
+        assert_eq!(summary["warnings"], 1);
+        assert_eq!(summary["parse_errors"], 1);
+        assert_eq!(summary["suppressed"]["comment"], 1);
+        assert_eq!(summary["suppressed"]["baseline"], 0);
+        assert_eq!(summary["by_rule"]["keyword_forbidden_always_comb"], 2);
+        assert_eq!(summary["by_file"][&f_1], 1);
+        assert!(summary["elapsed_seconds"].is_f64());
+
+        // Without `--summary`, reports are unchanged.
+        let args = ["svlint", "--format", "jsonl", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let _ = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!printer.read_to_string().unwrap().contains("\"summary\""));
+
+        let args = ["svlint", "--format", "jsonl", "--summary", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let _ = run_opt_config(&mut printer, &opt, config);
+        let stdout = printer.read_to_string().unwrap();
+        let record: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap()).unwrap();
+        assert_eq!(record["type"], "summary");
+        assert_eq!(record["warnings"], 1);
+    } // }}}
+
+    #[test]
+    fn overrides() {
+        // {{{
+        let f_1 = resources_path("eventlist_or.sv");
+        let args = ["svlint", "--oneline", &f_1];
+        let opt = Opt::parse_from(args.iter());
+
+        // Disabled for files matching a regex.
+        let s = "[syntaxrules]\neventlist_or = true\n\
+                 [[overrides]]\nregexes = [\"eventlist_or\\\\.sv$\"]\n\
+                 [overrides.syntaxrules]\neventlist_or = false\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        assert_eq!(printer.read_to_string().unwrap(), "");
+
+        // Where several overrides match, the last takes precedence.
+        let s = "[syntaxrules]\neventlist_or = true\n\
+                 [[overrides]]\nglobs = [\"**/resources/*.sv\"]\n\
+                 [overrides.syntaxrules]\neventlist_or = false\n\
+                 [[overrides]]\nglobs = [\"**/eventlist_or.sv\"]\n\
+                 [overrides.syntaxrules]\neventlist_or = \"warning\"\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+        assert!(printer.read_to_string().unwrap().starts_with("Warning\t"));
+
+        // Files which don't match use the base configuration.
+        let s = "[syntaxrules]\neventlist_or = true\n\
+                 [[overrides]]\nglobs = [\"tb/**\"]\n\
+                 [overrides.syntaxrules]\neventlist_or = false\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+
+        // Unknown rules in an override are an error.
+        let s = "[[overrides]]\nglobs = [\"tb/**\"]\n\
+                 [overrides.syntaxrules]\ncase_defualt = true\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.is_err());
+
+        // Where the configuration isn't checked, e.g. by a library user, the
+        // linter ignores them.
+        let mut linter = Linter::new(config);
+        let path = Path::new("tb/foo.sv");
+        let _ = linter.textrules_check(TextRuleEvent::StartOfFile, path, &0);
+    } // }}}
+
+    #[test]
+    fn custom_syntaxrules() {
+        // {{{
+        let f_1 = resources_path("eventlist_or.sv");
+        let args = ["svlint", "--oneline", &f_1];
+        let opt = Opt::parse_from(args.iter());
+
+        // Matched by node kind, ancestor kinds, and text.
+        let s = "[[custom_syntaxrules]]\nname = \"no_async_reset\"\nhint = \"Use a synchronous reset.\"\n\
+                 node = \"EventExpressionExpression\"\nwithin = \"AlwaysConstruct\"\nregex = \"arst\"\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config);
+        assert!(!ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert_eq!(stdout.lines().count(), 1);
+        assert!(stdout.contains("Use a synchronous reset."));
+
+        // Nodes outside the `within` kinds, or whose text doesn't match, pass.
+        for (within, regex) in [("ModuleDeclarationAnsi", "rst"), ("InitialConstruct", "arst")] {
+            let s = format!(
+                "[[custom_syntaxrules]]\nname = \"no_async_reset\"\nhint = \"\"\n\
+                 node = \"EventExpressionExpression\"\nwithin = [\"{}\"]\nregex = \"{}\"\n",
+                within, regex
+            );
+            let config: Config = toml::from_str(&s).unwrap();
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config);
+            assert_eq!(ret.unwrap(), within == "InitialConstruct");
+        }
+
+        // Custom rules are controlled by comments, like built-in rules.
+        let text = std::fs::read_to_string(&f_1)
+            .unwrap()
+            .replace("  always_ff", "  // svlint off-next-line no_always_ff\n  always_ff");
+        let f_2 = env::temp_dir().join(format!("svlint_custom_{}.sv", process::id()));
+        std::fs::write(&f_2, text).unwrap();
+        let f_2 = f_2.to_string_lossy().to_string();
+        let s = "[[custom_syntaxrules]]\nname = \"no_always_ff\"\nhint = \"\"\nnode = \"AlwaysConstruct\"\n\
+                 regex = \"^always_ff\"\nseverity = \"warning\"\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let args = ["svlint", "--oneline", &f_2];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config);
+        assert!(ret.unwrap());
+        assert_eq!(printer.read_to_string().unwrap(), "");
+        std::fs::remove_file(&f_2).unwrap();
+
+        // Rules may be given by a query instead.
+        let s = "[[custom_syntaxrules]]\nname = \"no_async_reset\"\nhint = \"Use a synchronous reset.\"\n\
+                 query = 'AlwaysConstruct EventExpressionExpression[text ~ \"arst\"]'\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let args = ["svlint", "--oneline", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config);
+        assert!(!ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert_eq!(stdout.lines().count(), 1);
+        assert!(stdout.contains(":6:32\t"));
+
+        // Rules keep their place in the syntax tree while disabled by a
+        // control comment.
+        let f_3 = env::temp_dir().join(format!("svlint_custom_syntaxrules_{}.sv", process::id()));
+        let f_3 = f_3.to_string_lossy().to_string();
+        let text = [
+            "/* svlint off all */",
+            "module A (input a);",
+            "endmodule",
+            "/* svlint on all */",
+            "module B (input b);",
+            "  always_comb begin",
+            "    x = a;",
+            "    /* svlint off all */",
+            "  end",
+            "  assign y = b; /* svlint on all */",
+            "  assign z = b;",
+            "endmodule",
+        ];
+        std::fs::write(&f_3, text.join("\n")).unwrap();
+        let s = "[[custom_syntaxrules]]\nname = \"no_ansi\"\nhint = \"\"\nquery = \"ModuleDeclarationAnsi\"\n\
+                 [[custom_syntaxrules]]\nname = \"no_assign_in_always\"\nhint = \"\"\nnode = \"NetAssignment\"\n\
+                 within = \"AlwaysConstruct\"\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let args = ["svlint", "--oneline", &f_3];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config);
+        assert!(!ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert_eq!(stdout.lines().count(), 1);
+        assert!(stdout.contains(":5:1\t"));
+        std::fs::remove_file(&f_3).unwrap();
+
+        let cases = [
+            ("name = \"NoAlways\"\nhint = \"\"\nnode = \"AlwaysConstruct\"\n", "must contain only lowercase"),
+            ("name = \"no_always\"\nhint = \"\"\n", "either `node` or `query` is required"),
+            ("name = \"eventlist_or\"\nhint = \"\"\nnode = \"AlwaysConstruct\"\n", "is a built-in rule"),
+            ("name = \"no_always\"\nhint = \"\"\nnode = \"always_construct\"\n", "isn't a node kind"),
+            (
+                "name = \"no_always\"\nhint = \"\"\nnode = \"AlwaysConstuct\"\n",
+                "isn't a node kind, did you mean \"AlwaysConstruct\"?",
+            ),
+            (
+                "name = \"no_always\"\nhint = \"\"\nnode = \"AlwaysKeyword\"\nwithin = [\"ModuleDeclarationAnsii\"]\n",
+                "\"ModuleDeclarationAnsii\" isn't a node kind, did you mean \"ModuleDeclarationAnsi\"?",
+            ),
+        ];
+        for (s, expected) in cases {
+            let config: Config = toml::from_str(&format!("[[custom_syntaxrules]]\n{}", s)).unwrap();
+            let err = config.validate().unwrap_err();
+            assert!(err.to_string().starts_with("invalid custom_syntaxrules[0]: "));
+            assert!(err.to_string().contains(expected));
+        }
+
+        // Every kind in a syntax tree is known.
+        let text = std::fs::read_to_string(&f_1).unwrap();
+        let (syntax_tree, _) =
+            parse_sv_str(&text, &f_1, &HashMap::new(), &Vec::<PathBuf>::new(), false, false).unwrap();
+        for node in &syntax_tree {
+            let kind = node.to_string();
+            assert!(svlint::custom::node_kinds().any(|x| x == kind), "{}", kind);
+        }
+    } // }}}
+
+    #[test]
+    fn query() {
+        // {{{
+        let f_1 = resources_path("eventlist_or.sv");
+
+        // Captures are printed below each match, located by their first token.
+        let args = [
+            "svlint",
+            "--query",
+            "ModuleDeclarationAnsi@module PortIdentifier[ident ~ \"^o_\"]@port",
+            &f_1,
+        ];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, Config::new());
+        assert!(ret.unwrap());
+        let expected = format!(
+            "{}:4:22: PortIdentifier: o_q\n    @module 1:1: module M\n    @port 4:22: o_q\n",
+            f_1
+        );
+        assert_eq!(printer.read_to_string().unwrap(), expected);
+
+        // Nesting, `:has()`, and `:not()`.
+        let cases = [
+            ("AlwaysConstruct EventExpressionExpression", 2),
+            ("AlwaysConstruct > EventExpressionExpression", 0),
+            ("EventExpression:has(EventExpressionOr)", 1),
+            ("EventExpressionExpression[text !~ \"clk\"], PortIdentifier[ident = \"i_clk\"]", 2),
+            ("AlwaysConstruct:not([text ~ \"^always_ff\"])", 0),
+        ];
+        for (query, expected) in cases {
+            let args = ["svlint", "--format", "jsonl", "--query", query, &f_1];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, Config::new());
+            assert_eq!(ret.unwrap(), expected > 0);
+            assert_eq!(printer.read_to_string().unwrap().lines().count(), expected);
+        }
+
+        let args = ["svlint", "--format", "json", "--query", "EventExpressionOr", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, Config::new());
+        assert!(ret.unwrap());
+        let records: Vec<serde_json::Value> =
+            serde_json::from_str(&printer.read_to_string().unwrap()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["line"], 6);
+        assert_eq!(records[0]["column"], 15);
+        assert_eq!(records[0]["text"], "posedge i_clk or posedge i_arst");
+
+        // Matches in other files don't hide a file which can't be parsed.
+        let f_2 = resources_path("parse_error.sv");
+        let args = ["svlint", "--query", "EventExpressionOr", &f_1, &f_2];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, Config::new());
+        assert!(!ret.unwrap());
+        let stdout = printer.read_to_string().unwrap();
+        assert!(stdout.contains("EventExpressionOr: posedge i_clk or posedge i_arst"));
+        assert!(stdout.contains("parse error"));
+    } // }}}
+
+    #[test]
+    fn cache() {
+        // {{{
+        let dir = env::temp_dir().join(format!("svlint_cache_{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cache_dir = dir.join("cache").to_string_lossy().to_string();
+        let f_1 = dir.join("top.sv");
+        let f_inc = dir.join("inc.svh");
+        let text = std::fs::read_to_string(resources_path("eventlist_or.sv")).unwrap();
+        std::fs::write(&f_1, format!("`include \"{}\"\n{}", f_inc.display(), text)).unwrap();
+        std::fs::write(&f_inc, "`define FOO\n").unwrap();
+        let f_1 = f_1.to_string_lossy().to_string();
+
+        let run = |config: &Config, extra: &[&str]| {
+            let mut args = vec!["svlint", "--oneline", "--cache-dir", &cache_dir];
+            args.extend(extra);
+            args.push(&f_1);
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            (ret.unwrap(), printer.read_to_string().unwrap())
+        };
+        let mut config = Config::new();
+        config.syntaxrules.eventlist_or = Severity::Error;
+
+        let (pass, stdout) = run(&config, &[]);
+        assert!(!pass);
+        assert!(stdout.contains("hint: Use comma"));
+
+        // Unchanged files are replayed from the cache, which is shown by
+        // changing the cached hint.
+        let entries: Vec<PathBuf> = std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|x| x.unwrap().path())
+            .filter(|x| x.extension().and_then(|x| x.to_str()) == Some("json"))
+            .collect();
+        assert_eq!(entries.len(), 1);
+        let entry = std::fs::read_to_string(&entries[0]).unwrap();
+        std::fs::write(&entries[0], entry.replace("Use comma", "Cached comma")).unwrap();
+        let (pass, stdout) = run(&config, &[]);
+        assert!(!pass);
+        assert!(stdout.contains("hint: Cached comma"));
+
+        // Everything else which affects the result is part of the key.
+        let args = ["svlint", "--oneline", &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(!ret.unwrap());
+        assert!(printer.read_to_string().unwrap().contains("hint: Use comma"));
+        let mut config_warning = config.clone();
+        config_warning.syntaxrules.eventlist_or = Severity::Warning;
+        let (pass, stdout) = run(&config_warning, &[]);
+        assert!(pass);
+        assert!(stdout.starts_with("Warning\t"));
+        let (pass, _) = run(&config, &["-D", "BAR"]);
+        assert!(!pass);
+        std::fs::write(&entries[0], entry.replace("Use comma", "Cached comma")).unwrap();
+        std::fs::write(&f_inc, "`define FOO 1\n").unwrap();
+        let (_, stdout) = run(&config, &[]);
+        assert!(stdout.contains("hint: Use comma"));
+
+        // Files which include a file named by a macro aren't cached.
+        std::fs::remove_file(&entries[0]).unwrap();
+        let s = format!("`define INC \"{}\"\n`include `INC\n{}", f_inc.display(), text);
+        std::fs::write(&f_1, s).unwrap();
+        let (pass, stdout) = run(&config, &[]);
+        assert!(!pass);
+        assert!(stdout.contains("hint: Use comma"));
+        assert!(!entries[0].exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    } // }}}
+
+    #[test]
+    fn watch() {
+        // {{{
+        let dir = env::temp_dir().join(format!("svlint_watch_{}", process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let f_1 = dir.join("top.sv");
+        let f_inc = dir.join("inc.svh");
+        let f_config = dir.join(".svlint.toml");
+        let f_base = dir.join("base.toml");
+        std::fs::write(&f_1, "`include \"inc.svh\"\nmodule M;\nendmodule\n").unwrap();
+        std::fs::write(&f_inc, "").unwrap();
+        std::fs::write(&f_config, "extends = [\"base.toml\"]\n").unwrap();
+        std::fs::write(&f_base, "").unwrap();
+
+        // Included files are found in include directories, and are watched
+        // even when they don't exist.
+        // Every configuration file is watched, including those extended.
+        let incdir = dir.to_string_lossy().to_string();
+        let args = ["svlint", "-i", &incdir, f_1.to_str().unwrap()];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let (_, config_files) = load_config(&mut printer, &opt, Some(&f_config)).unwrap();
+        let watched = watched_files(&opt, &config_files);
+        assert_eq!(watched, vec![f_config.clone(), f_base.clone(), f_inc.clone(), f_1.clone()]);
+
+        let mut watcher = Watcher::default();
+        watcher.watch(watched);
+        assert!(watcher.changed().is_empty());
+        std::fs::write(&f_inc, "`define FOO\n").unwrap();
+        assert_eq!(watcher.changed(), vec![f_inc.clone()]);
+        assert!(watcher.changed().is_empty());
+        std::fs::write(&f_base, "[syntaxrules]\n").unwrap();
+        assert_eq!(watcher.changed(), vec![f_base.clone()]);
+        std::fs::write(&f_config, "").unwrap();
+        assert_eq!(watcher.changed(), vec![f_config.clone()]);
+        std::fs::remove_file(&f_config).unwrap();
+        assert_eq!(watcher.changed(), vec![f_config.clone()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    } // }}}
+
+    #[test]
+    fn lsp() {
+        // {{{
+        let f_config = env::temp_dir().join(format!("svlint_lsp_{}.toml", process::id()));
+        std::fs::write(&f_config, "[syntaxrules]\neventlist_or = true\n").unwrap();
+        let f_config = f_config.to_string_lossy().to_string();
+
+        let message = |x: serde_json::Value| {
+            let s = x.to_string();
+            format!("Content-Length: {}\r\n\r\n{}", s.len(), s)
+        };
+        // The document doesn't exist on disk, so it can only be linted from
+        // the text given by the client.
+        let uri = "file:///svlint/no%20such/dir/top.sv";
+        let text = "module M;\n  always @(a or b) q1 <= d;\nendmodule\n";
+        let input = [
+            message(serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}})),
+            message(serde_json::json!({"jsonrpc": "2.0", "method": "initialized", "params": {}})),
+            message(serde_json::json!({"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+                "textDocument": {"uri": uri, "languageId": "systemverilog", "version": 1, "text": text},
+            }})),
+            message(serde_json::json!({"jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": {"uri": uri, "version": 2},
+                "contentChanges": [{"text": "module M;\nendmodule\n"}],
+            }})),
+            message(serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "unknown"})),
+            message(serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"})),
+            message(serde_json::json!({"jsonrpc": "2.0", "method": "exit"})),
+        ]
+        .concat();
+
+        let args = ["svlint", "--lsp", "--config", &f_config];
+        let opt = Opt::parse_from(args.iter());
+        let mut output = Vec::new();
+        let ret = run_lsp(io::Cursor::new(input), &mut output, &opt);
+        assert!(ret.unwrap());
+
+        let output = String::from_utf8(output).unwrap();
+        let messages: Vec<serde_json::Value> = output
+            .split("Content-Length: ")
+            .filter(|x| !x.is_empty())
+            .map(|x| serde_json::from_str(x.split_once("\r\n\r\n").unwrap().1).unwrap())
+            .collect();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0]["result"]["serverInfo"]["name"], "svlint");
+
+        let diagnostics = &messages[1]["params"]["diagnostics"];
+        assert_eq!(messages[1]["params"]["uri"], uri);
+        assert_eq!(diagnostics.as_array().unwrap().len(), 1);
+        assert_eq!(diagnostics[0]["code"], "eventlist_or");
+        assert_eq!(diagnostics[0]["range"]["start"], serde_json::json!({"line": 1, "character": 11}));
+        assert_eq!(diagnostics[0]["severity"], 1);
+        assert!(diagnostics[0]["data"]["hint"].as_str().unwrap().contains("comma"));
+        assert!(!diagnostics[0]["data"]["reason"].as_str().unwrap().is_empty());
+
+        assert_eq!(messages[2]["params"]["diagnostics"], serde_json::json!([]));
+        assert_eq!(messages[3]["error"]["code"], -32601);
+        assert_eq!(messages[4]["id"], 3);
+
+        std::fs::remove_file(&f_config).unwrap();
+    } // }}}
+
+    #[test]
+    fn stdin() {
+        // {{{
+        let config: Config = toml::from_str(
+            "[option]\nexclude_paths = [\"excluded/\"]\n\
+             [syntaxrules]\neventlist_or = true\nmodule_identifier_matches_filename = true\n",
+        )
+        .unwrap();
+        let text = "module M;\n  always @(a or b) q1 <= d;\nendmodule\n";
+
+        // The path needn't exist, as failures are reported against the text.
+        let run = |stdin_filename: &str, extra: &[&str]| {
+            let mut args = vec!["svlint", "--stdin-filename", stdin_filename];
+            args.extend(extra);
+            args.push("-");
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config_stdin(&mut printer, &opt, config.clone(), Some(text));
+            (ret, printer.read_to_string().unwrap())
+        };
+
+        let (ret, stdout) = run("no/such/dir/M.sv", &["--oneline"]);
+        assert!(!ret.unwrap());
+        assert!(stdout.contains("no/such/dir/M.sv:2:12"));
+        assert!(!stdout.contains("module_identifier_matches_filename"));
+
+        let (_, stdout) = run("no/such/dir/N.sv", &["--format", "jsonl"]);
+        let records: Vec<serde_json::Value> = stdout.lines().map(|x| serde_json::from_str(x).unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["rule"], "module_identifier_matches_filename");
+        assert_eq!(records[1]["source"], "  always @(a or b) q1 <= d;");
+
+        let (ret, stdout) = run("excluded/M.sv", &[]);
+        assert!(ret.unwrap());
+        assert_eq!(stdout, "");
+
+        let (ret, _) = run("no/such/dir/M.sv", &["--fix"]);
+        assert!(ret.is_err());
+
+        let (ret, stdout) = run("no/such/dir/M.sv", &["--query", "EventExpressionOr"]);
+        assert!(ret.unwrap());
+        assert_eq!(stdout, "no/such/dir/M.sv:2:12: EventExpressionOr: a or b\n");
+    } // }}}
+
+    #[test]
+    fn config_extends() {
+        // {{{
+        // Every built-in ruleset is a valid configuration.
+        for (_, toml) in svlint::config::RULESETS {
+            let config: Result<Config, _> = toml::from_str(toml);
+            assert!(config.is_ok());
+        }
+
+        let dir = env::temp_dir().join(format!("svlint_extends_{}", process::id()));
+        std::fs::create_dir_all(dir.join("common")).unwrap();
+        let f_base = dir.join("common").join("base.toml");
+        let f_top = dir.join(".svlint.toml");
+
+        // Later layers take precedence, and relative paths are from the file
+        // which extends them.
+        let s = "extends = [\"style\"]\n[option]\ntextwidth = 100\n[syntaxrules]\neventlist_or = true\n";
+        std::fs::write(&f_base, s).unwrap();
+        let s = "extends = [\"common/base.toml\"]\n[option]\nindent = 4\n[syntaxrules]\nstyle_indent = false\n";
+        std::fs::write(&f_top, s).unwrap();
+        let config = Config::load(&f_top).unwrap();
+        assert_eq!(config.option.textwidth, 100);
+        assert_eq!(config.option.indent, 4);
+        assert_eq!(config.textrules.style_textwidth, Severity::Error);
+        assert_eq!(config.syntaxrules.eventlist_or, Severity::Error);
+        assert_eq!(config.syntaxrules.style_indent, Severity::Off);
+
+        let s = "extends = [\"../.svlint.toml\"]\n";
+        std::fs::write(&f_base, s).unwrap();
+        let err = Config::load(&f_top).unwrap_err();
+        assert!(err.to_string().starts_with("cyclic extends: "));
+
+        let s = "extends = [\"styel\"]\n";
+        std::fs::write(&f_base, s).unwrap();
+        let err = Config::load(&f_top).unwrap_err();
+        assert!(err.to_string().contains("did you mean \"style\"?"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    } // }}}
+
+    #[test]
+    fn config_validation() {
+        // {{{
+        let f_1 = env::temp_dir().join(format!("svlint_validation_{}.toml", process::id()));
+        let cases = [
+            (
+                "[syntaxrules]\ncase_defualt = true\n",
+                "unknown key `syntaxrules.case_defualt`, did you mean `case_default`?",
+            ),
+            (
+                "[option]\nprefix_inpt = \"i_\"\n",
+                "unknown key `option.prefix_inpt`, did you mean `prefix_input`?",
+            ),
+            (
+                "[[overrides]]\nglobs = [\"tb/**\"]\n[overrides.textrules]\nstyle_textwidht = true\n",
+                "unknown key `overrides[0].textrules.style_textwidht`, did you mean `style_textwidth`?",
+            ),
+            (
+                "[option]\nre_required_port_input = \"^[a-z\"\n",
+                "invalid regex in `option.re_required_port_input`",
+            ),
+        ];
+        for (s, expected) in cases {
+            std::fs::write(&f_1, s).unwrap();
+            let err = Config::load(&f_1).unwrap_err();
+            assert!(format!("{:#}", err).contains(expected));
+        }
+
+        // Obsolete rule names are still accepted, to be reported as renamed.
+        std::fs::write(&f_1, "[syntaxrules]\ngenerate_keyword = true\n").unwrap();
+        assert!(Config::load(&f_1).is_ok());
+        std::fs::remove_file(&f_1).unwrap();
+
+        let s = "[[overrides]]\nglobs = [\"tb/**\"]\n[overrides.option]\nre_forbidden_task = \"(\"\n";
+        let config: Config = toml::from_str(s).unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("`overrides[0].option.re_forbidden_task`"));
+    } // }}}
+
+    #[test]
+    fn explain() {
+        // {{{
+        let args = ["svlint", "--explain", "eventlist_or"];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt(&mut printer, &opt);
+        assert!(ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        assert!(stdout.starts_with("## Syntax Rule: `eventlist_or`\n"));
+        assert!(stdout.contains("### Hint\n\nUse comma event expression separator instead of `or`.\n"));
+        assert!(stdout.contains("### Enabled by Rulesets\n\nDaveMcEwan-design, style\n"));
+        assert!(stdout.contains("### Pass Example (1 of 1)\n"));
+        assert!(stdout.contains("### Fail Example (5 of 5)\n"));
+        assert!(stdout.contains("### Explanation\n"));
+
+        let args = ["svlint", "--explain", "style_textwidht"];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let err = run_opt(&mut printer, &opt).unwrap_err();
+        assert!(err.to_string().contains("did you mean \"style_textwidth\"?"));
+    } // }}}
+
+    #[test]
+    fn list_rules() {
+        // {{{
+        let mut config = Config::new();
+        config.syntaxrules.eventlist_or = Severity::Warning;
+        config.textrules.style_textwidth = Severity::Error;
+
+        let args = ["svlint", "--list-rules"];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config.clone());
+        assert!(ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        assert_eq!(stdout.lines().count(), RULE_DOCS.len());
+        let re = Regex::new(r"(?m)^eventlist_or +syntax +functional +warning +builtin +options=- +rulesets=DaveMcEwan-design,style$").unwrap();
+        assert!(re.is_match(&stdout));
+        let re = Regex::new(r"(?m)^style_textwidth +text +style +error +builtin +options=textwidth +rulesets=").unwrap();
+        assert!(re.is_match(&stdout));
+        let re = Regex::new(r"(?m)^generate_for_with_label +syntax +naming +off +builtin +options=prefix_label ").unwrap();
+        assert!(re.is_match(&stdout));
+
+        let args = ["svlint", "--list-rules", "--format", "json"];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let ret = run_opt_config(&mut printer, &opt, config);
+        assert!(ret.unwrap());
+
+        let stdout = printer.read_to_string().unwrap();
+        let rules: Vec<serde_json::Value> = serde_json::from_str(&stdout).unwrap();
+        let rule = rules.iter().find(|x| x["name"] == "eventlist_or").unwrap();
+        assert_eq!(rule["type"], "syntax");
+        assert_eq!(rule["category"], "functional");
+        assert_eq!(rule["severity"], "warning");
+        assert_eq!(rule["enabled"], true);
+        assert_eq!(rule["source"], "builtin");
+        let rule = rules.iter().find(|x| x["name"] == "style_indent").unwrap();
+        assert_eq!(rule["category"], "style");
+        assert_eq!(rule["options"], serde_json::json!(["indent"]));
+        assert_eq!(rule["enabled"], false);
+    } // }}}
+
+    #[test]
+    fn plugin_interface() {
+        // {{{
+        use svlint::linter::{Rule, SyntaxRule};
+        use svlint::plugin::PluginRule;
+
+        // Anything which isn't a compatible plugin is rejected with an error.
+        let f_1 = resources_path("eventlist_or.sv");
+        let args = ["svlint", "-p", &f_1, &f_1];
+        let opt = Opt::parse_from(args.iter());
+        let mut printer = Printer::new(true);
+        let err = run_opt_config(&mut printer, &opt, Config::new()).unwrap_err();
+        assert!(err.to_string().starts_with("failed to load plugin '"));
+
+        // Rules behave the same when called through the plugin vtable.
+        let rule = Config::gen_all_syntaxrules()
+            .into_iter()
+            .find(|x| x.name() == "eventlist_or")
+            .unwrap();
+        let mut rule: Box<dyn SyntaxRule> = match PluginRule::syntax(rule).into_rule() {
+            Rule::Syntax(x) => unsafe { Box::from_raw(x) },
+            Rule::Text(_) => unreachable!(),
+        };
+        let option = ConfigOption::default();
+        assert_eq!(rule.name(), "eventlist_or");
+        assert_eq!(rule.hint(&option), "Use comma event expression separator instead of `or`.");
+
+        let text = std::fs::read_to_string(&f_1).unwrap();
+        let (syntax_tree, _) =
+            parse_sv_str(&text, &f_1, &HashMap::new(), &Vec::<PathBuf>::new(), false, false).unwrap();
+        let mut n_failures = 0;
+        for node in syntax_tree.into_iter().event() {
+            match rule.check(&syntax_tree, &node, &option) {
+                svlint::linter::SyntaxRuleResult::Pass => {}
+                _ => n_failures += 1,
+            }
+        }
+        assert_eq!(n_failures, 1);
+    } // }}}
+
+    #[test]
+    fn jobs_ordering() {
+        // {{{
+        let config = Config::new().enable_all();
+
+        let f_1 = resources_path("eventlist_or.sv");
+        let f_2 = resources_path("parse_error.sv");
+        let f_3 = resources_path("must_pass_all_rulesets.sv");
+
+        let mut stdouts = vec![];
+        for jobs in ["1", "3"] {
+            let mut args = vec!["svlint", "--oneline", "-j", jobs];
+            args.push(&f_1);
+            args.push(&f_2);
+            args.push(&f_3);
+            args.push(&f_1);
+            let opt = Opt::parse_from(args.iter());
+
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(!ret.unwrap());
+            stdouts.push(printer.read_to_string().unwrap());
+        }
+
+        assert_eq!(stdouts[0], stdouts[1]);
+    } // }}}
+
+    #[test]
+    fn jobs_error() {
+        // {{{
+        let config = Config::new();
+
+        // A file which can't be read stops the run, whatever the number of
+        // jobs.
+        let f_1 = resources_path("missing.sv");
+        let f_2 = resources_path("eventlist_or.sv");
+        for jobs in ["1", "2"] {
+            let args = ["svlint", "-j", jobs, &f_1, &f_2, &f_2, &f_2];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(ret.is_err());
+            assert_eq!(printer.read_to_string().unwrap(), "");
+        }
+    } // }}}
+
+    #[test]
+    fn jobs_compilation_unit() {
+        // {{{
+        let config = Config::new();
+
+        let f_1 = resources_path("define_foo.svh");
+        let f_2 = resources_path("ifndef_foo.sv");
+
+        // Each file is independent, so the macro isn't defined, whatever the
+        // number of jobs.
+        for jobs in ["1", "2"] {
+            let args = ["svlint", "-j", jobs, &f_1, &f_2];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(!ret.unwrap());
+
+            let args = ["svlint", "-j", jobs, "--compilation-unit", &f_1, &f_2];
+            let opt = Opt::parse_from(args.iter());
+            let mut printer = Printer::new(true);
+            let ret = run_opt_config(&mut printer, &opt, config.clone());
+            assert!(ret.unwrap());
+        }
+    } // }}}
+
     #[test]
     fn lint_gbk_encoded_verilog() {
         use std::fs::File;
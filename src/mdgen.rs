@@ -1,11 +1,17 @@
 #![allow(dead_code)]
 
+mod baseline;
 mod config;
+mod custom;
+mod docs;
 mod linter;
+mod plugin;
 mod printer;
+mod query;
 mod rules;
 
 use crate::config::{Config, ConfigOption};
+use crate::docs::rule_category;
 use crate::linter::{TextRule, SyntaxRule};
 use regex::Regex;
 use std::env;
@@ -173,24 +179,15 @@ fn write_md_syntaxrules(o: &mut File, syntaxrules: Vec<Box<dyn SyntaxRule>>) ->
 fn partition_syntaxrules(
     syntaxrules: Vec<Box<dyn SyntaxRule>>,
 ) -> (Vec<Box<dyn SyntaxRule>>, Vec<Box<dyn SyntaxRule>>, Vec<Box<dyn SyntaxRule>>) {
-    let style_prefixes = ["style_", "tab_"].join("|");
-    let re_style: Regex = Regex::new(format!("^({})", style_prefixes).as_str()).unwrap();
-
-    let naming_prefixes = ["prefix_", "lowercamelcase_", "uppercamelcase_", "re_"].join("|");
-    let re_naming: Regex =
-        Regex::new(format!("(^({})|_with_label$)", naming_prefixes).as_str()).unwrap();
-
     let mut part_style: Vec<Box<dyn SyntaxRule>> = Vec::new();
     let mut part_naming: Vec<Box<dyn SyntaxRule>> = Vec::new();
     let mut part_functional: Vec<Box<dyn SyntaxRule>> = Vec::new();
 
     for rule in syntaxrules {
-        if re_style.is_match(&rule.name()) {
-            part_style.push(rule);
-        } else if re_naming.is_match(&rule.name()) {
-            part_naming.push(rule);
-        } else {
-            part_functional.push(rule);
+        match rule_category(&rule.name()) {
+            "style" => part_style.push(rule),
+            "naming" => part_naming.push(rule),
+            _ => part_functional.push(rule),
         }
     }
 
@@ -0,0 +1,320 @@
+use crate::config::ConfigOption;
+use crate::linter::{Rule, SyntaxRule, SyntaxRuleResult, TextRule, TextRuleEvent, TextRuleResult};
+use std::collections::hash_map::DefaultHasher;
+use std::ffi::{c_char, c_void, CStr};
+use std::hash::{Hash, Hasher};
+use sv_parser::{NodeEvent, SyntaxTree};
+
+// Version of the interface between svlint and plugins, i.e. the exported
+// symbols and the `#[repr(C)]` types in this module.
+// This is incremented on any change to the interface, and is checked before
+// anything else is read from a plugin.
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+// Macro for use within plugins, which exports the plugin interface for the
+// given rules, each of which must implement either `TextRule` or `SyntaxRule`,
+// and `Default`.
+// Example usage at the top level of a plugin crate (`crate-type = ["cdylib"]`):
+//    svlint_plugin!(
+//        SamplePlugin,
+//        AnotherPlugin,
+//    );
+#[macro_export]
+macro_rules! svlint_plugin {
+    ( $( $x:ty ),* $(,)? ) => {
+        #[no_mangle]
+        pub extern "C" fn svlint_plugin_abi_version() -> u32 {
+            $crate::plugin::PLUGIN_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn svlint_plugin_info() -> $crate::plugin::PluginInfo {
+            $crate::plugin::PluginInfo::current()
+        }
+
+        #[no_mangle]
+        pub extern "C" fn svlint_plugin_rules(
+            push: extern "C" fn(*mut ::std::ffi::c_void, $crate::plugin::PluginRule),
+            ctx: *mut ::std::ffi::c_void,
+        ) {
+            use $crate::linter::{SyntaxRule as _, TextRule as _};
+            $(
+                push(ctx, $crate::plugin::PluginRule::new(<$x>::default().into_rule()));
+            )*
+        }
+    };
+}
+
+// Details of the svlint build which a plugin was built against.
+// Rules are given svlint's and sv-parser's Rust types, e.g. `SyntaxTree`,
+// which are only compatible between identical builds, so a plugin is only
+// loaded where its `build_id` is the same as svlint's.
+#[repr(C)]
+pub struct PluginInfo {
+    pub svlint_version: *const c_char,
+    pub rustc_version: *const c_char,
+    pub build_id: u64,
+}
+
+impl PluginInfo {
+    pub fn current() -> PluginInfo {
+        PluginInfo {
+            svlint_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char,
+            rustc_version: concat!(env!("SVLINT_RUSTC_VERSION"), "\0").as_ptr() as *const c_char,
+            build_id: build_id(),
+        }
+    }
+
+    pub fn svlint_version(&self) -> String {
+        unsafe { CStr::from_ptr(self.svlint_version) }.to_string_lossy().to_string()
+    }
+
+    pub fn rustc_version(&self) -> String {
+        unsafe { CStr::from_ptr(self.rustc_version) }.to_string_lossy().to_string()
+    }
+}
+
+// Identity of the types shared with plugins, which differs between builds
+// with different versions of svlint, rustc, or sv-parser.
+fn build_id() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    env!("SVLINT_RUSTC_VERSION").hash(&mut hasher);
+    env!("SVLINT_SV_PARSER_VERSION").hash(&mut hasher);
+    hasher.finish()
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PluginRuleKind {
+    Text,
+    Syntax,
+}
+
+// FFI-safe vtable of one rule from a plugin.
+// The functions are implemented in the plugin, so that each rule is only used
+// by code compiled together with it.
+// Arguments and results are passed by pointer, where `rule` points to a
+// `Box<dyn TextRule>` or `Box<dyn SyntaxRule>` according to `kind`, and the
+// other pointers are to the types noted below.
+#[repr(C)]
+pub struct PluginRule {
+    pub kind: PluginRuleKind,
+    pub rule: *mut c_void,
+
+    // Text rules: (rule, null, &TextRuleEvent, &ConfigOption, &mut TextRuleResult)
+    // Syntax rules: (rule, &SyntaxTree, &NodeEvent, &ConfigOption, &mut SyntaxRuleResult)
+    pub check: unsafe extern "C" fn(*mut c_void, *const c_void, *const c_void, *const c_void, *mut c_void),
+
+    // (rule, &mut String)
+    pub name: unsafe extern "C" fn(*const c_void, *mut c_void),
+
+    // (rule, &ConfigOption, &mut String)
+    pub hint: unsafe extern "C" fn(*const c_void, *const c_void, *mut c_void),
+
+    // (rule, &mut String)
+    pub reason: unsafe extern "C" fn(*const c_void, *mut c_void),
+
+    // (rule)
+    pub drop: unsafe extern "C" fn(*mut c_void),
+}
+
+// Rules are only used by one thread at a time, like built-in rules.
+unsafe impl Send for PluginRule {}
+unsafe impl Sync for PluginRule {}
+
+impl PluginRule {
+    pub fn new(rule: Rule) -> PluginRule {
+        match rule {
+            Rule::Text(x) => PluginRule::text(unsafe { Box::from_raw(x) }),
+            Rule::Syntax(x) => PluginRule::syntax(unsafe { Box::from_raw(x) }),
+        }
+    }
+
+    pub fn text(rule: Box<dyn TextRule>) -> PluginRule {
+        PluginRule {
+            kind: PluginRuleKind::Text,
+            rule: Box::into_raw(Box::new(rule)) as *mut c_void,
+            check: text_check,
+            name: text_name,
+            hint: text_hint,
+            reason: text_reason,
+            drop: text_drop,
+        }
+    }
+
+    pub fn syntax(rule: Box<dyn SyntaxRule>) -> PluginRule {
+        PluginRule {
+            kind: PluginRuleKind::Syntax,
+            rule: Box::into_raw(Box::new(rule)) as *mut c_void,
+            check: syntax_check,
+            name: syntax_name,
+            hint: syntax_hint,
+            reason: syntax_reason,
+            drop: syntax_drop,
+        }
+    }
+
+    // Wrap the vtable as a rule for use by the linter in the same way as a
+    // built-in rule.
+    pub fn into_rule(self) -> Rule {
+        match self.kind {
+            PluginRuleKind::Text => Rule::Text(Box::into_raw(Box::new(self))),
+            PluginRuleKind::Syntax => Rule::Syntax(Box::into_raw(Box::new(self))),
+        }
+    }
+
+    fn string(&self, f: unsafe extern "C" fn(*const c_void, *mut c_void)) -> String {
+        let mut ret = String::new();
+        unsafe { f(self.rule, &mut ret as *mut String as *mut c_void) };
+        ret
+    }
+
+    fn hint_string(&self, option: &ConfigOption) -> String {
+        let mut ret = String::new();
+        unsafe {
+            (self.hint)(
+                self.rule,
+                option as *const ConfigOption as *const c_void,
+                &mut ret as *mut String as *mut c_void,
+            )
+        };
+        ret
+    }
+}
+
+impl Drop for PluginRule {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.rule) };
+    }
+}
+
+impl TextRule for PluginRule {
+    fn check(&mut self, event: TextRuleEvent, option: &ConfigOption) -> TextRuleResult {
+        let mut ret = TextRuleResult::Pass;
+        unsafe {
+            (self.check)(
+                self.rule,
+                std::ptr::null(),
+                &event as *const TextRuleEvent as *const c_void,
+                option as *const ConfigOption as *const c_void,
+                &mut ret as *mut TextRuleResult as *mut c_void,
+            )
+        };
+        ret
+    }
+
+    fn name(&self) -> String {
+        self.string(self.name)
+    }
+
+    fn hint(&self, option: &ConfigOption) -> String {
+        self.hint_string(option)
+    }
+
+    fn reason(&self) -> String {
+        self.string(self.reason)
+    }
+}
+
+impl SyntaxRule for PluginRule {
+    fn check(
+        &mut self,
+        syntax_tree: &SyntaxTree,
+        event: &NodeEvent,
+        option: &ConfigOption,
+    ) -> SyntaxRuleResult {
+        let mut ret = SyntaxRuleResult::Pass;
+        unsafe {
+            (self.check)(
+                self.rule,
+                syntax_tree as *const SyntaxTree as *const c_void,
+                event as *const NodeEvent as *const c_void,
+                option as *const ConfigOption as *const c_void,
+                &mut ret as *mut SyntaxRuleResult as *mut c_void,
+            )
+        };
+        ret
+    }
+
+    fn name(&self) -> String {
+        self.string(self.name)
+    }
+
+    fn hint(&self, option: &ConfigOption) -> String {
+        self.hint_string(option)
+    }
+
+    fn reason(&self) -> String {
+        self.string(self.reason)
+    }
+}
+
+// Implementations of the vtable, compiled into the plugin.
+
+unsafe extern "C" fn text_check(
+    rule: *mut c_void,
+    _syntax_tree: *const c_void,
+    event: *const c_void,
+    option: *const c_void,
+    result: *mut c_void,
+) {
+    let rule = &mut *(rule as *mut Box<dyn TextRule>);
+    let event = *(event as *const TextRuleEvent);
+    let option = &*(option as *const ConfigOption);
+    *(result as *mut TextRuleResult) = rule.check(event, option);
+}
+
+unsafe extern "C" fn text_name(rule: *const c_void, result: *mut c_void) {
+    let rule = &*(rule as *const Box<dyn TextRule>);
+    *(result as *mut String) = rule.name();
+}
+
+unsafe extern "C" fn text_hint(rule: *const c_void, option: *const c_void, result: *mut c_void) {
+    let rule = &*(rule as *const Box<dyn TextRule>);
+    let option = &*(option as *const ConfigOption);
+    *(result as *mut String) = rule.hint(option);
+}
+
+unsafe extern "C" fn text_reason(rule: *const c_void, result: *mut c_void) {
+    let rule = &*(rule as *const Box<dyn TextRule>);
+    *(result as *mut String) = rule.reason();
+}
+
+unsafe extern "C" fn text_drop(rule: *mut c_void) {
+    drop(Box::from_raw(rule as *mut Box<dyn TextRule>));
+}
+
+unsafe extern "C" fn syntax_check(
+    rule: *mut c_void,
+    syntax_tree: *const c_void,
+    event: *const c_void,
+    option: *const c_void,
+    result: *mut c_void,
+) {
+    let rule = &mut *(rule as *mut Box<dyn SyntaxRule>);
+    let syntax_tree = &*(syntax_tree as *const SyntaxTree);
+    let event = &*(event as *const NodeEvent);
+    let option = &*(option as *const ConfigOption);
+    *(result as *mut SyntaxRuleResult) = rule.check(syntax_tree, event, option);
+}
+
+unsafe extern "C" fn syntax_name(rule: *const c_void, result: *mut c_void) {
+    let rule = &*(rule as *const Box<dyn SyntaxRule>);
+    *(result as *mut String) = rule.name();
+}
+
+unsafe extern "C" fn syntax_hint(rule: *const c_void, option: *const c_void, result: *mut c_void) {
+    let rule = &*(rule as *const Box<dyn SyntaxRule>);
+    let option = &*(option as *const ConfigOption);
+    *(result as *mut String) = rule.hint(option);
+}
+
+unsafe extern "C" fn syntax_reason(rule: *const c_void, result: *mut c_void) {
+    let rule = &*(rule as *const Box<dyn SyntaxRule>);
+    *(result as *mut String) = rule.reason();
+}
+
+unsafe extern "C" fn syntax_drop(rule: *mut c_void) {
+    drop(Box::from_raw(rule as *mut Box<dyn SyntaxRule>));
+}
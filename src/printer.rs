@@ -1,10 +1,15 @@
-use crate::linter::LintFailed;
+use crate::baseline::{baseline_path, Fingerprinter};
+use crate::config::Severity;
+use crate::linter::{read_source, LintFailed};
 use anyhow::{Context, Error};
 use colored::*;
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::cmp;
-use std::fs::File;
-use std::io::{Read, Write};
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use term::{self, color, StdoutTerminal};
 
 // -------------------------------------------------------------------------------------------------
@@ -38,6 +43,95 @@ enum TermCapture<C, N> {
     Noncapturable(N),
 }
 
+// -------------------------------------------------------------------------------------------------
+// OutputFormat
+// -------------------------------------------------------------------------------------------------
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Sarif,
+    Json,
+    Jsonl,
+    Checkstyle,
+    Junit,
+    Gitlab,
+}
+
+// Failure to preprocess or parse a file, i.e. anything reported by sv-parser
+// instead of by a rule.
+// Where `path` and `beg` are both known, the error is attributed to a
+// position in a source file.
+#[derive(Clone, Debug)]
+pub struct ParseFailed {
+    pub path: Option<PathBuf>,
+    pub beg: Option<usize>,
+    pub kind: String,
+    pub message: String,
+}
+
+// Counts over a whole run, printed by `--summary`.
+// Failures are counted once they're reported, i.e. after any suppression.
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub files: usize,
+    pub parse_errors: usize,
+    pub suppressed_by_comment: usize,
+    pub suppressed_by_baseline: usize,
+    pub suppressed_by_diff: usize,
+    pub elapsed: Duration,
+    pub by_severity: BTreeMap<&'static str, usize>,
+    pub by_rule: BTreeMap<String, usize>,
+    pub by_file: BTreeMap<String, usize>,
+    pub by_directory: BTreeMap<String, usize>,
+}
+
+impl Summary {
+    pub fn add_failed(&mut self, failed: &LintFailed) {
+        let path = failed.path.to_string_lossy().to_string();
+        let directory = match failed.path.parent() {
+            Some(x) if !x.as_os_str().is_empty() => x.to_string_lossy().to_string(),
+            _ => String::from("."),
+        };
+
+        *self.by_severity.entry(failed.severity.as_str()).or_default() += 1;
+        *self.by_rule.entry(failed.name.clone()).or_default() += 1;
+        *self.by_file.entry(path).or_default() += 1;
+        *self.by_directory.entry(directory).or_default() += 1;
+    }
+
+    pub fn failures(&self) -> usize {
+        self.by_severity.values().sum()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "files": self.files,
+            "failures": self.failures(),
+            "errors": self.by_severity.get("error").copied().unwrap_or(0),
+            "warnings": self.by_severity.get("warning").copied().unwrap_or(0),
+            "info": self.by_severity.get("info").copied().unwrap_or(0),
+            "parse_errors": self.parse_errors,
+            "suppressed": {
+                "comment": self.suppressed_by_comment,
+                "baseline": self.suppressed_by_baseline,
+                "diff": self.suppressed_by_diff,
+            },
+            "elapsed_seconds": self.elapsed.as_secs_f64(),
+            "by_rule": self.by_rule,
+            "by_file": self.by_file,
+            "by_directory": self.by_directory,
+        })
+    }
+
+    // Most frequent first, then alphabetically.
+    fn ranked(counts: &BTreeMap<String, usize>) -> Vec<(&String, &usize)> {
+        let mut ret: Vec<(&String, &usize)> = counts.iter().collect();
+        ret.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        ret
+    }
+}
+
 // -------------------------------------------------------------------------------------------------
 // Printer
 // -------------------------------------------------------------------------------------------------
@@ -47,6 +141,15 @@ static CHAR_LF: u8 = 0x0a;
 
 pub struct Printer {
     term: TermCapture<Vec<u8>, Option<Box<StdoutTerminal>>>,
+    format: OutputFormat,
+    failed: Vec<LintFailed>,
+    parse_failed: Vec<ParseFailed>,
+    // Each linted file, with the number of failures and errors collected
+    // before it, so that collected items can be grouped by linted file.
+    linted: Vec<(PathBuf, usize, usize)>,
+    // Text of sources which aren't read from disk, i.e. stdin, and of those
+    // read for failures printed as they're found.
+    sources: HashMap<PathBuf, String>,
 }
 
 impl Printer {
@@ -58,9 +161,43 @@ impl Printer {
             } else {
                 TermCapture::Noncapturable(term::stdout())
             },
+            format: OutputFormat::Text,
+            failed: Vec::new(),
+            parse_failed: Vec::new(),
+            linted: Vec::new(),
+            sources: HashMap::new(),
         }
     }
 
+    // Select how failures are reported.
+    // Any format other than `Text` collects failures and errors until
+    // `print_report()` is called at the end of a run.
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    // Report failures in `path` against `s` instead of the file on disk.
+    pub fn set_source(&mut self, path: &Path, s: &str) {
+        self.sources.insert(path.to_path_buf(), s.to_string());
+    }
+
+    // Note the start of each linted file's results, for formats which report
+    // every file, whether or not anything fails.
+    pub fn begin_file(&mut self, path: &Path) {
+        self.linted
+            .push((path.to_path_buf(), self.failed.len(), self.parse_failed.len()));
+    }
+
+    // Clear the terminal, and anything collected for the report, before the
+    // results are printed again by `--watch`.
+    pub fn clear_screen(&mut self) {
+        self.failed.clear();
+        self.parse_failed.clear();
+        self.linted.clear();
+        self.sources.clear();
+        self.write("\x1b[2J\x1b[H", None);
+    }
+
     pub fn read_to_string(&self) -> Option<String> {
         match self.term {
             TermCapture::Capturable(ref buf) => Some(String::from_utf8_lossy(buf).to_string()),
@@ -172,7 +309,7 @@ impl Printer {
         }
     }
 
-    fn get_pos(src: &str, print_pos: usize) -> Option<(usize, usize)> {
+    pub fn get_pos(src: &str, print_pos: usize) -> Option<(usize, usize)> {
         let mut pos = 0;
         let mut column = 1;
         let mut last_lf = None;
@@ -205,6 +342,35 @@ impl Printer {
         None
     }
 
+    // Text of the whole line containing `print_pos`, without line endings.
+    fn get_line(src: &str, print_pos: usize) -> Option<String> {
+        let bytes = src.as_bytes();
+        if print_pos > bytes.len() {
+            return None;
+        }
+
+        let beg = bytes[..print_pos]
+            .iter()
+            .rposition(|&x| x == CHAR_LF)
+            .map_or(0, |x| x + 1);
+        let end = bytes[print_pos..]
+            .iter()
+            .position(|&x| x == CHAR_CR || x == CHAR_LF)
+            .map_or(bytes.len(), |x| print_pos + x);
+
+        Some(String::from_utf8_lossy(&bytes[beg..end]).to_string())
+    }
+
+    // Headers are colored the same as the equivalent messages from
+    // `print_warning()` and `print_info()`.
+    fn header_color(header: &str) -> Color {
+        match header {
+            "Warning" => Color::BrightYellow,
+            "Info" => Color::BrightGreen,
+            _ => Color::BrightRed,
+        }
+    }
+
     #[cfg_attr(tarpaulin, skip)]
     fn print_oneline(
         &mut self,
@@ -215,7 +381,7 @@ impl Printer {
         hint: Option<&str>,
     ) {
         Printer::with_pos(src, print_pos, |pos, column, row, next_crlf, _last_lf| {
-            self.write(header, Some(Color::BrightRed));
+            self.write(header, Some(Printer::header_color(header)));
             self.write(
                 &format!("\t{}:{}:{}", path.to_string_lossy(), column, row),
                 Some(Color::BrightBlue),
@@ -247,7 +413,7 @@ impl Printer {
         reason: Option<&str>,
     ) {
         Printer::with_pos(src, print_pos, |pos, column, row, next_crlf, last_lf| {
-            self.write(header, Some(Color::BrightRed));
+            self.write(header, Some(Printer::header_color(header)));
 
             let beg = if let Some(last_lf) = last_lf {
                 if next_crlf > last_lf {
@@ -335,19 +501,34 @@ impl Printer {
         oneline: bool,
         github_actions: bool,
     ) -> Result<(), Error> {
-        let mut f = File::open(&failed.path)
-            .with_context(|| format!("failed to open: '{}'", failed.path.to_string_lossy()))?;
-        let mut s = String::new();
-        let _ = f.read_to_string(&mut s);
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::Jsonl => {
+                let record = Printer::failed_json(&mut self.sources, failed)?;
+                return self.println(&record.to_string());
+            }
+            _ => {
+                self.failed.push(failed.clone());
+                return Ok(());
+            }
+        }
+
+        let s = self.read_source(&failed.path)?;
+
+        let header = match failed.severity {
+            Severity::Warning => "Warning",
+            Severity::Info => "Info",
+            _ => "Fail",
+        };
 
         if oneline {
-            self.print_oneline(&s, failed.beg, "Fail", &failed.path, Some(&failed.hint));
+            self.print_oneline(&s, failed.beg, header, &failed.path, Some(&failed.hint));
         } else {
             self.print_pretty(
                 &s,
                 failed.beg,
                 failed.len,
-                "Fail",
+                header,
                 &failed.name,
                 &failed.path,
                 Some(&failed.hint),
@@ -357,8 +538,14 @@ impl Printer {
 
         if github_actions {
             if let Some((row, column)) = Printer::get_pos(&s, failed.beg) {
+                let command = match failed.severity {
+                    Severity::Warning => "warning",
+                    Severity::Info => "notice",
+                    _ => "error",
+                };
                 println!(
-                    "::error file={},line={},col={}::{}",
+                    "::{} file={},line={},col={}::{}",
+                    command,
                     failed.path.to_string_lossy(),
                     column,
                     row,
@@ -377,10 +564,7 @@ impl Printer {
         error_pos: usize,
         oneline: bool,
     ) -> Result<(), Error> {
-        let mut f = File::open(path)
-            .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?;
-        let mut s = String::new();
-        let _ = f.read_to_string(&mut s);
+        let s = self.read_source(path)?;
 
         if oneline {
             self.print_oneline(&s, error_pos, "Error", path, Some("parse error"));
@@ -397,10 +581,7 @@ impl Printer {
         error_pos: usize,
         oneline: bool,
     ) -> Result<(), Error> {
-        let mut f = File::open(path)
-            .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?;
-        let mut s = String::new();
-        let _ = f.read_to_string(&mut s);
+        let s = self.read_source(path)?;
 
         if oneline {
             self.print_oneline(&s, error_pos, "Error", path, Some("preprocess error"));
@@ -419,6 +600,590 @@ impl Printer {
         Ok(())
     }
 
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn print_parse_failed(
+        &mut self,
+        failed: &ParseFailed,
+        oneline: bool,
+    ) -> Result<(), Error> {
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::Jsonl => {
+                let record = Printer::parse_failed_json(&mut self.sources, failed)?;
+                return self.println(&record.to_string());
+            }
+            _ => {
+                self.parse_failed.push(failed.clone());
+                return Ok(());
+            }
+        }
+
+        match (&failed.path, failed.beg, failed.kind.as_str()) {
+            (Some(path), Some(beg), "parse") => self.print_parse_error(path, beg, oneline),
+            (Some(path), Some(beg), "preprocess") => {
+                self.print_preprocess_error(path, beg, oneline)
+            }
+            _ => self.print_error(&failed.message),
+        }
+    }
+
+    // Print everything collected by `print_failed()` and `print_parse_failed()`
+    // as a single document in the selected (non-`Text`) format, along with the
+    // summary if one is given.
+    #[cfg_attr(tarpaulin, skip)]
+    pub fn print_report(&mut self, summary: Option<&Summary>) -> Result<(), Error> {
+        match self.format {
+            OutputFormat::Text => match summary {
+                Some(x) => self.print_summary(x),
+                None => Ok(()),
+            },
+            OutputFormat::Jsonl => match summary {
+                Some(x) => {
+                    let mut record = x.to_json();
+                    record["type"] = json!("summary");
+                    self.println(&record.to_string())
+                }
+                None => Ok(()),
+            },
+            OutputFormat::Sarif => self.print_sarif(summary),
+            OutputFormat::Json => self.print_json(summary),
+            OutputFormat::Checkstyle => self.print_checkstyle(),
+            OutputFormat::Junit => self.print_junit(summary),
+            OutputFormat::Gitlab => self.print_gitlab(),
+        }
+    }
+
+    // GitLab Code Quality report, i.e. a JSON array with an issue per failure
+    // or preprocessor/parser error.
+    // Fingerprints are derived from the same location-tolerant identity as
+    // baseline entries, so an issue keeps its fingerprint when lines are
+    // added or removed elsewhere in the file.
+    fn print_gitlab(&mut self) -> Result<(), Error> {
+        let mut sources = self.sources.clone();
+        let mut fingerprinter = Fingerprinter::default();
+        for (path, s) in &self.sources {
+            fingerprinter.set_source(path, s);
+        }
+
+        let mut issues = Vec::new();
+        for failed in &self.failed {
+            let src = Printer::source(&mut sources, &failed.path)?;
+            let line = Printer::get_pos(src, failed.beg).map_or(1, |(_, line)| line);
+            let x = fingerprinter.fingerprint(failed)?;
+            let fingerprint = format!("{}\0{}\0{}\0{}", x.path, x.rule, x.source, x.occurrence);
+            let severity = match failed.severity {
+                Severity::Warning => "minor",
+                Severity::Info => "info",
+                _ => "major",
+            };
+            issues.push(json!({
+                "type": "issue",
+                "description": failed.hint,
+                "check_name": failed.name,
+                "fingerprint": format!("{:x}", Sha256::digest(fingerprint.as_bytes())),
+                "severity": severity,
+                "location": {
+                    "path": x.path,
+                    "lines": { "begin": line },
+                },
+            }));
+        }
+
+        for failed in &self.parse_failed {
+            let path = failed.path.as_deref().map(baseline_path);
+            let mut line = 1;
+            if let (Some(path), Some(beg)) = (&failed.path, failed.beg) {
+                let src = Printer::source(&mut sources, path)?;
+                line = Printer::get_pos(src, beg).map_or(1, |(_, line)| line);
+            }
+            let fingerprint = format!(
+                "{}\0{}\0{}\0{}",
+                path.as_deref().unwrap_or(""),
+                failed.kind,
+                failed.message,
+                line
+            );
+            issues.push(json!({
+                "type": "issue",
+                "description": failed.message,
+                "check_name": failed.kind,
+                "fingerprint": format!("{:x}", Sha256::digest(fingerprint.as_bytes())),
+                "severity": "critical",
+                "location": {
+                    "path": path.unwrap_or_default(),
+                    "lines": { "begin": line },
+                },
+            }));
+        }
+
+        self.println(&serde_json::to_string_pretty(&issues)?)
+    }
+
+    // Ranges of collected failures and errors which belong to each linted
+    // file.
+    fn linted_ranges(&self) -> Vec<(PathBuf, std::ops::Range<usize>, std::ops::Range<usize>)> {
+        let mut ret = Vec::new();
+        for (i, (path, failed_beg, parse_failed_beg)) in self.linted.iter().enumerate() {
+            let (failed_end, parse_failed_end) = match self.linted.get(i + 1) {
+                Some((_, x, y)) => (*x, *y),
+                None => (self.failed.len(), self.parse_failed.len()),
+            };
+            ret.push((
+                path.clone(),
+                *failed_beg..failed_end,
+                *parse_failed_beg..parse_failed_end,
+            ));
+        }
+        ret
+    }
+
+    // One `<file>` per linted file, and per included file where anything
+    // fails, with an `<error>` per failure or preprocessor/parser error.
+    fn print_checkstyle(&mut self) -> Result<(), Error> {
+        let mut sources = self.sources.clone();
+
+        let mut files: Vec<(PathBuf, Vec<String>)> = Vec::new();
+        fn file_errors<'a>(
+            files: &'a mut Vec<(PathBuf, Vec<String>)>,
+            path: &Path,
+        ) -> &'a mut Vec<String> {
+            let i = match files.iter().position(|(x, _)| x == path) {
+                Some(i) => i,
+                None => {
+                    files.push((path.to_path_buf(), Vec::new()));
+                    files.len() - 1
+                }
+            };
+            &mut files[i].1
+        }
+
+        for (linted, failed_range, parse_failed_range) in self.linted_ranges() {
+            let _ = file_errors(&mut files, &linted);
+
+            for failed in &self.failed[failed_range] {
+                let src = Printer::source(&mut sources, &failed.path)?;
+                let (column, line) = Printer::get_pos(src, failed.beg).unwrap_or((0, 0));
+                let error = format!(
+                    "<error line=\"{}\" column=\"{}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>",
+                    line,
+                    column,
+                    failed.severity.as_str(),
+                    xml_escape(&failed.hint),
+                    xml_escape(&failed.name),
+                );
+                file_errors(&mut files, &failed.path).push(error);
+            }
+
+            // Errors without a position are attributed to the linted file.
+            for failed in &self.parse_failed[parse_failed_range] {
+                let path = failed.path.clone().unwrap_or_else(|| linted.clone());
+                let pos = match failed.beg {
+                    Some(beg) => Printer::get_pos(Printer::source(&mut sources, &path)?, beg),
+                    None => None,
+                };
+                let (column, line) = pos.unwrap_or((0, 0));
+                let error = format!(
+                    "<error line=\"{}\" column=\"{}\" severity=\"error\" message=\"{}\" source=\"{}\"/>",
+                    line,
+                    column,
+                    xml_escape(&failed.message),
+                    xml_escape(&failed.kind),
+                );
+                file_errors(&mut files, &path).push(error);
+            }
+        }
+
+        let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        s += "<checkstyle version=\"4.3\">\n";
+        for (path, errors) in &files {
+            let name = xml_escape(&path.to_string_lossy());
+            if errors.is_empty() {
+                s += &format!("  <file name=\"{}\"/>\n", name);
+            } else {
+                s += &format!("  <file name=\"{}\">\n", name);
+                for error in errors {
+                    s += &format!("    {}\n", error);
+                }
+                s += "  </file>\n";
+            }
+        }
+        s += "</checkstyle>";
+
+        self.println(&s)
+    }
+
+    // One `<testcase>` per linted file, which fails with a `<failure>` listing
+    // each failure of a rule with severity "error", or with an `<error>` for
+    // each preprocessor/parser error.
+    // Failures of rules with other severities don't fail the testcase, so
+    // they're listed in `<system-out>`.
+    fn print_junit(&mut self, summary: Option<&Summary>) -> Result<(), Error> {
+        let mut sources = self.sources.clone();
+
+        let mut testcases = Vec::new();
+        let (mut n_failures, mut n_errors) = (0, 0);
+        for (linted, failed_range, parse_failed_range) in self.linted_ranges() {
+            let name = xml_escape(&linted.to_string_lossy());
+
+            let mut failures = Vec::new();
+            let mut others = Vec::new();
+            for failed in &self.failed[failed_range] {
+                let src = Printer::source(&mut sources, &failed.path)?;
+                let (column, line) = Printer::get_pos(src, failed.beg).unwrap_or((0, 0));
+                let text = format!(
+                    "{}:{}:{}: {}: {}",
+                    failed.path.to_string_lossy(),
+                    line,
+                    column,
+                    failed.name,
+                    failed.hint
+                );
+                if failed.severity == Severity::Error {
+                    failures.push(text);
+                } else {
+                    others.push(format!("{}: {}", failed.severity, text));
+                }
+            }
+
+            let mut body = String::new();
+            if !failures.is_empty() {
+                n_failures += 1;
+                body += &format!(
+                    "      <failure message=\"{} rule failure(s)\" type=\"svlint\">{}</failure>\n",
+                    failures.len(),
+                    xml_escape(&failures.join("\n"))
+                );
+            }
+            let parse_failed = &self.parse_failed[parse_failed_range];
+            if !parse_failed.is_empty() {
+                n_errors += 1;
+            }
+            for failed in parse_failed {
+                let path = failed.path.clone().unwrap_or_else(|| linted.clone());
+                let mut text = path.to_string_lossy().to_string();
+                if let Some(beg) = failed.beg {
+                    let src = Printer::source(&mut sources, &path)?;
+                    if let Some((column, line)) = Printer::get_pos(src, beg) {
+                        text += &format!(":{}:{}", line, column);
+                    }
+                }
+                body += &format!(
+                    "      <error message=\"{}\" type=\"{}\">{}: {}</error>\n",
+                    xml_escape(&failed.message),
+                    xml_escape(&failed.kind),
+                    xml_escape(&text),
+                    xml_escape(&failed.message)
+                );
+            }
+            if !others.is_empty() {
+                body += &format!(
+                    "      <system-out>{}</system-out>\n",
+                    xml_escape(&others.join("\n"))
+                );
+            }
+
+            if body.is_empty() {
+                testcases.push(format!("    <testcase name=\"{}\" classname=\"svlint\"/>\n", name));
+            } else {
+                testcases.push(format!(
+                    "    <testcase name=\"{}\" classname=\"svlint\">\n{}    </testcase>\n",
+                    name, body
+                ));
+            }
+        }
+
+        let counts = format!(
+            "tests=\"{}\" failures=\"{}\" errors=\"{}\"",
+            testcases.len(),
+            n_failures,
+            n_errors
+        );
+        let time = match summary {
+            Some(x) => format!(" time=\"{:.3}\"", x.elapsed.as_secs_f64()),
+            None => String::new(),
+        };
+
+        let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        s += &format!("<testsuites name=\"svlint\" {}{}>\n", counts, time);
+        s += &format!("  <testsuite name=\"svlint\" {}{}>\n", counts, time);
+        if let Some(x) = summary {
+            s += "    <properties>\n";
+            for (name, value) in x.to_json().as_object().unwrap() {
+                if !value.is_object() {
+                    s += &format!(
+                        "      <property name=\"{}\" value=\"{}\"/>\n",
+                        name,
+                        xml_escape(&value.to_string())
+                    );
+                }
+            }
+            s += "    </properties>\n";
+        }
+        for testcase in testcases {
+            s += &testcase;
+        }
+        s += "  </testsuite>\n";
+        s += "</testsuites>";
+
+        self.println(&s)
+    }
+
+    fn print_summary(&mut self, summary: &Summary) -> Result<(), Error> {
+        let count = |x: &str| summary.by_severity.get(x).copied().unwrap_or(0);
+
+        self.write("Summary", Some(Color::BrightWhite));
+        self.write("\n", Some(Color::Reset));
+        self.println(&format!("  files linted: {}", summary.files))?;
+        self.println(&format!(
+            "  failures:     {} ({} error, {} warning, {} info)",
+            summary.failures(),
+            count("error"),
+            count("warning"),
+            count("info")
+        ))?;
+        self.println(&format!("  parse errors: {}", summary.parse_errors))?;
+        self.println(&format!(
+            "  suppressed:   {} ({} by control comments, {} by baseline, {} outside diff)",
+            summary.suppressed_by_comment + summary.suppressed_by_baseline + summary.suppressed_by_diff,
+            summary.suppressed_by_comment,
+            summary.suppressed_by_baseline,
+            summary.suppressed_by_diff
+        ))?;
+        self.println(&format!("  elapsed time: {:.2}s", summary.elapsed.as_secs_f64()))?;
+
+        for (title, counts) in [
+            ("Failures by rule", &summary.by_rule),
+            ("Failures by file", &summary.by_file),
+            ("Failures by directory", &summary.by_directory),
+        ] {
+            if counts.is_empty() {
+                continue;
+            }
+            self.write(title, Some(Color::BrightWhite));
+            self.write("\n", Some(Color::Reset));
+            for (name, n) in Summary::ranked(counts) {
+                self.println(&format!("  {:6} {}", n, name))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn print_json(&mut self, summary: Option<&Summary>) -> Result<(), Error> {
+        let mut sources = self.sources.clone();
+
+        let mut failures = Vec::new();
+        for failed in &self.failed {
+            failures.push(Printer::failed_json(&mut sources, failed)?);
+        }
+
+        let mut errors = Vec::new();
+        for failed in &self.parse_failed {
+            errors.push(Printer::parse_failed_json(&mut sources, failed)?);
+        }
+
+        let mut report = json!({
+            "failures": failures,
+            "errors": errors,
+        });
+        if let Some(x) = summary {
+            report["summary"] = x.to_json();
+        }
+
+        self.println(&serde_json::to_string_pretty(&report)?)
+    }
+
+    // One record per rule failure, shared by the JSON and JSON Lines formats.
+    fn failed_json(
+        sources: &mut HashMap<PathBuf, String>,
+        failed: &LintFailed,
+    ) -> Result<serde_json::Value, Error> {
+        let src = Printer::source(sources, &failed.path)?;
+        let pos = Printer::get_pos(src, failed.beg);
+
+        Ok(json!({
+            "type": "failure",
+            "path": failed.path.to_string_lossy(),
+            "beg": failed.beg,
+            "line": pos.map(|(_, line)| line),
+            "column": pos.map(|(column, _)| column),
+            "len": failed.len,
+            "rule": failed.name,
+            "severity": failed.severity.as_str(),
+            "hint": failed.hint,
+            "reason": failed.reason,
+            "source": Printer::get_line(src, failed.beg),
+        }))
+    }
+
+    // One record per preprocessor/parser error, shared by the JSON and JSON
+    // Lines formats.
+    fn parse_failed_json(
+        sources: &mut HashMap<PathBuf, String>,
+        failed: &ParseFailed,
+    ) -> Result<serde_json::Value, Error> {
+        let mut record = json!({
+            "type": "error",
+            "kind": failed.kind,
+            "path": failed.path.as_ref().map(|x| x.to_string_lossy()),
+            "beg": failed.beg,
+            "line": null,
+            "column": null,
+            "message": failed.message,
+            "source": null,
+        });
+
+        if let (Some(path), Some(beg)) = (&failed.path, failed.beg) {
+            let src = Printer::source(sources, path)?;
+            if let Some((column, line)) = Printer::get_pos(src, beg) {
+                record["line"] = json!(line);
+                record["column"] = json!(column);
+            }
+            record["source"] = json!(Printer::get_line(src, beg));
+        }
+
+        Ok(record)
+    }
+
+    fn print_sarif(&mut self, summary: Option<&Summary>) -> Result<(), Error> {
+        let mut sources = self.sources.clone();
+
+        let mut rules = Vec::new();
+        let mut rule_indices: HashMap<String, usize> = HashMap::new();
+        let mut results = Vec::new();
+        for failed in &self.failed {
+            let rule_index = *rule_indices.entry(failed.name.clone()).or_insert_with(|| {
+                rules.push(json!({
+                    "id": failed.name,
+                    "shortDescription": { "text": failed.hint },
+                    "help": { "text": failed.reason },
+                }));
+                rules.len() - 1
+            });
+
+            let src = Printer::source(&mut sources, &failed.path)?;
+            let level = match failed.severity {
+                Severity::Warning => "warning",
+                Severity::Info => "note",
+                _ => "error",
+            };
+            results.push(json!({
+                "ruleId": failed.name,
+                "ruleIndex": rule_index,
+                "level": level,
+                "message": { "text": failed.hint },
+                "locations": [
+                    Printer::sarif_location(&failed.path, src, Some(failed.beg), failed.len),
+                ],
+            }));
+        }
+
+        let mut notifications = Vec::new();
+        for failed in &self.parse_failed {
+            let mut notification = json!({
+                "level": "error",
+                "descriptor": { "id": failed.kind },
+                "message": { "text": failed.message },
+            });
+            // Where a file is missing, e.g. an include, it's given without a
+            // region.
+            if let Some(path) = &failed.path {
+                let location = match failed.beg {
+                    Some(beg) => match Printer::source(&mut sources, path) {
+                        Ok(src) => Printer::sarif_location(path, src, Some(beg), 1),
+                        Err(_) => Printer::sarif_location(path, "", None, 1),
+                    },
+                    None => Printer::sarif_location(path, "", None, 1),
+                };
+                notification["locations"] = json!([location]);
+            }
+            notifications.push(notification);
+        }
+
+        let mut log = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "svlint",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    },
+                },
+                "invocations": [{
+                    "executionSuccessful": self.parse_failed.is_empty(),
+                    "toolExecutionNotifications": notifications,
+                }],
+                "results": results,
+            }],
+        });
+
+        // SARIF has no place for these counts, so they're kept in the run's
+        // property bag.
+        if let Some(x) = summary {
+            log["runs"][0]["properties"] = json!({ "summary": x.to_json() });
+        }
+
+        self.println(&serde_json::to_string_pretty(&log)?)
+    }
+
+    fn sarif_location(
+        path: &Path,
+        src: &str,
+        beg: Option<usize>,
+        len: usize,
+    ) -> serde_json::Value {
+        let mut location = json!({
+            "physicalLocation": {
+                "artifactLocation": {
+                    "uri": path.to_string_lossy().replace('\\', "/"),
+                },
+            },
+        });
+
+        if let Some(beg) = beg {
+            if let Some((start_column, start_line)) = Printer::get_pos(src, beg) {
+                let mut region = json!({
+                    "startLine": start_line,
+                    "startColumn": start_column,
+                });
+
+                // Only give the end of a region which doesn't span lines.
+                let end = cmp::min(beg + len, src.len());
+                if !src.as_bytes()[beg..end].contains(&CHAR_LF) {
+                    region["endLine"] = json!(start_line);
+                    region["endColumn"] = json!(start_column + end - beg);
+                }
+                location["physicalLocation"]["region"] = region;
+            }
+        }
+
+        location
+    }
+
+    // Text of a source file, either as given by `set_source()` or read from
+    // disk.
+    fn read_source(&self, path: &Path) -> Result<String, Error> {
+        if let Some(x) = self.sources.get(path) {
+            return Ok(x.clone());
+        }
+        read_source(path).with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))
+    }
+
+    // Read each source file only once when building a report.
+    fn source<'a>(
+        sources: &'a mut HashMap<PathBuf, String>,
+        path: &Path,
+    ) -> Result<&'a str, Error> {
+        if !sources.contains_key(path) {
+            let s = read_source(path)
+                .with_context(|| format!("failed to open: '{}'", path.to_string_lossy()))?;
+            sources.insert(path.to_path_buf(), s);
+        }
+        Ok(&sources[path])
+    }
+
     #[cfg_attr(tarpaulin, skip)]
     pub fn print_error(&mut self, msg: &str) -> Result<(), Error> {
         self.write("Error", Some(Color::BrightRed));
@@ -429,6 +1194,12 @@ impl Printer {
 
     #[cfg_attr(tarpaulin, skip)]
     pub fn print_warning(&mut self, msg: &str) -> Result<(), Error> {
+        if self.format != OutputFormat::Text {
+            // Keep STDOUT parseable as a single document.
+            eprintln!("Warning: {}", msg);
+            return Ok(());
+        }
+
         self.write("Warning", Some(Color::BrightYellow));
         self.write(&format!(": {}", msg), Some(Color::BrightWhite));
         self.write("\n", Some(Color::Reset));
@@ -437,6 +1208,12 @@ impl Printer {
 
     #[cfg_attr(tarpaulin, skip)]
     pub fn print_info(&mut self, msg: &str) -> Result<(), Error> {
+        if self.format != OutputFormat::Text {
+            // Keep STDOUT parseable as a single document.
+            eprintln!("Info: {}", msg);
+            return Ok(());
+        }
+
         self.write("Info", Some(Color::BrightGreen));
         self.write(&format!(": {}", msg), Some(Color::BrightWhite));
         self.write("\n", Some(Color::Reset));
@@ -445,6 +1222,11 @@ impl Printer {
 
     #[cfg_attr(tarpaulin, skip)]
     pub fn print_error_type(&mut self, error: Error) -> Result<(), Error> {
+        if self.format != OutputFormat::Text {
+            eprintln!("Error: {:#}", error);
+            return Ok(());
+        }
+
         let mut cause = error.chain();
         self.write("Error", Some(Color::BrightRed));
         self.write(&format!(": {}", cause.next().unwrap()), Some(Color::BrightWhite));
@@ -468,3 +1250,18 @@ impl Printer {
         Ok(())
     }
 }
+
+fn xml_escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
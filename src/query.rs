@@ -0,0 +1,444 @@
+use anyhow::{anyhow, Error};
+use regex::Regex;
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use std::fmt;
+use std::str::FromStr;
+use sv_parser::{unwrap_node, NodeEvent, RefNode, SyntaxTree};
+
+// Pattern over the syntax tree, in a syntax similar to CSS selectors, which
+// selects nodes by kind, by their ancestors, and by their text.
+// Kinds are named as shown by `--dump-syntaxtree`.
+//
+//    query    := selector ("," selector)*
+//    selector := step (combinator step)*
+//    step     := (Kind | "*")? filter* ("@" capture)?
+//    filter   := "[" ("text" | "ident") ("=" | "!=" | "~" | "!~") string "]"
+//              | ":has(" query ")"
+//              | ":not(" step ")"
+//
+// Steps are separated by whitespace where the next step is any descendant of
+// the previous one, or by `>` where it's a direct child.
+// Examples:
+//    AlwaysConstruct[text ~ "^always_comb"] LoopStatement
+//    CaseStatementNormal:not(:has(CaseItemDefault))
+//    ModuleDeclarationAnsi@module PortIdentifier[ident !~ "^[io]_"]@port
+#[derive(Clone, Debug)]
+pub struct Query {
+    source: String,
+    selectors: Vec<Selector>,
+}
+
+#[derive(Clone, Debug)]
+struct Selector {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+    combinator: Combinator, // Relation to the previous step, if any.
+    kind: Option<String>,   // Any kind where `None`, i.e. `*`.
+    filters: Vec<Filter>,
+    capture: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Clone, Debug)]
+enum Filter {
+    Text {
+        attr: Attr,
+        pattern: Pattern,
+        negated: bool,
+    },
+    Has(Vec<Selector>),
+    Not(Box<Step>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Attr {
+    Text,  // Text of the whole node, without trailing whitespace.
+    Ident, // Text of the first identifier within the node.
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    Equal(String),
+    Regex(Regex),
+}
+
+// Node selected by a query, with the nodes selected by each named step.
+// `index` is the position of the node in the order that nodes are entered
+// when iterating over the syntax tree.
+pub struct QueryMatch<'a> {
+    pub index: usize,
+    pub node: RefNode<'a>,
+    pub captures: Vec<(String, RefNode<'a>)>,
+}
+
+impl Query {
+    pub fn parse(s: &str) -> Result<Query, Error> {
+        let mut parser = Parser {
+            s,
+            pos: 0,
+        };
+        let selectors = parser.query()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Query {
+            source: s.to_string(),
+            selectors,
+        })
+    }
+
+    // Every node selected by the query, in the order they're entered.
+    pub fn matches<'a>(&self, syntax_tree: &'a SyntaxTree) -> Vec<QueryMatch<'a>> {
+        search(&self.selectors, syntax_tree, syntax_tree.into_iter().event(), false)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Query, Error> {
+        Query::parse(s)
+    }
+}
+
+impl serde::Serialize for Query {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.source)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Query {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Query, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Query::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+// Match selectors against each node entered by `events`, where nodes are
+// checked against their ancestors within `events` only.
+// For `:has()`, the first event enters the node whose descendants are
+// searched, so it's skipped, and only the first match is needed.
+fn search<'a>(
+    selectors: &[Selector],
+    syntax_tree: &'a SyntaxTree,
+    events: impl Iterator<Item = NodeEvent<'a>>,
+    has: bool,
+) -> Vec<QueryMatch<'a>> {
+    let mut ret = Vec::new();
+    let mut ancestors: Vec<(RefNode<'a>, String)> = Vec::new();
+    let mut index = 0;
+
+    for event in events.skip(if has { 1 } else { 0 }) {
+        let node = match event {
+            NodeEvent::Enter(x) => x,
+            NodeEvent::Leave(_) => {
+                if ancestors.pop().is_none() {
+                    break; // Leaving the node given to `:has()`.
+                }
+                continue;
+            }
+        };
+        let kind = node.to_string();
+
+        for selector in selectors {
+            let mut captures = Vec::new();
+            let last = selector.steps.len() - 1;
+            if match_chain(selector, last, &node, &kind, &ancestors, syntax_tree, &mut captures) {
+                ret.push(QueryMatch {
+                    index,
+                    node: node.clone(),
+                    captures,
+                });
+                break;
+            }
+        }
+        if has && !ret.is_empty() {
+            break;
+        }
+
+        ancestors.push((node, kind));
+        index += 1;
+    }
+
+    ret
+}
+
+// Match step `i` of the selector against a node, and the previous steps
+// against its ancestors, outermost first.
+// Captures are only added where the whole chain matches.
+fn match_chain<'a>(
+    selector: &Selector,
+    i: usize,
+    node: &RefNode<'a>,
+    kind: &str,
+    ancestors: &[(RefNode<'a>, String)],
+    syntax_tree: &'a SyntaxTree,
+    captures: &mut Vec<(String, RefNode<'a>)>,
+) -> bool {
+    let step = &selector.steps[i];
+    if !match_step(step, node, kind, syntax_tree) {
+        return false;
+    }
+
+    let matched = if i == 0 {
+        true
+    } else {
+        match step.combinator {
+            Combinator::Child => match ancestors.split_last() {
+                Some(((x, k), rest)) => match_chain(selector, i - 1, x, k, rest, syntax_tree, captures),
+                None => false,
+            },
+            Combinator::Descendant => (0..ancestors.len()).rev().any(|j| {
+                let (x, k) = &ancestors[j];
+                match_chain(selector, i - 1, x, k, &ancestors[..j], syntax_tree, captures)
+            }),
+        }
+    };
+
+    if matched {
+        if let Some(name) = &step.capture {
+            captures.push((name.clone(), node.clone()));
+        }
+    }
+    matched
+}
+
+fn match_step<'a>(step: &Step, node: &RefNode<'a>, kind: &str, syntax_tree: &'a SyntaxTree) -> bool {
+    if matches!(&step.kind, Some(x) if x != kind) {
+        return false;
+    }
+
+    step.filters.iter().all(|filter| match filter {
+        Filter::Text {
+            attr,
+            pattern,
+            negated,
+        } => {
+            let text = match attr {
+                Attr::Text => syntax_tree.get_str_trim(vec![node.clone()]),
+                Attr::Ident => unwrap_node!(node.clone(), SimpleIdentifier, EscapedIdentifier)
+                    .and_then(|x| syntax_tree.get_str_trim(vec![x])),
+            }
+            .unwrap_or("");
+            let matched = match pattern {
+                Pattern::Equal(x) => text == x,
+                Pattern::Regex(x) => x.is_match(text),
+            };
+            matched != *negated
+        }
+        Filter::Has(selectors) => {
+            !search(selectors, syntax_tree, node.clone().into_iter().event(), true).is_empty()
+        }
+        Filter::Not(x) => !match_step(x, node, kind, syntax_tree),
+    })
+}
+
+struct Parser<'s> {
+    s: &'s str,
+    pos: usize, // Byte index of the next character.
+}
+
+impl<'s> Parser<'s> {
+    fn error(&self, msg: &str) -> Error {
+        anyhow!("invalid query at column {}: {}", self.s[..self.pos].chars().count() + 1, msg)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let rest = &self.s[self.pos..];
+        let n = rest.len() - rest.trim_start().len();
+        self.pos += n;
+        n > 0
+    }
+
+    fn eat(&mut self, x: &str) -> bool {
+        if self.s[self.pos..].starts_with(x) {
+            self.pos += x.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, x: &str) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.eat(x) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", x)))
+        }
+    }
+
+    fn word(&mut self) -> &'s str {
+        let rest = &self.s[self.pos..];
+        let n = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        self.pos += n;
+        &rest[..n]
+    }
+
+    fn query(&mut self) -> Result<Vec<Selector>, Error> {
+        let mut ret = vec![self.selector()?];
+        loop {
+            self.skip_whitespace();
+            if !self.eat(",") {
+                return Ok(ret);
+            }
+            ret.push(self.selector()?);
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, Error> {
+        self.skip_whitespace();
+        let mut steps = vec![self.step(Combinator::Descendant, true)?];
+        loop {
+            let space = self.skip_whitespace();
+            let combinator = if self.eat(">") {
+                self.skip_whitespace();
+                Combinator::Child
+            } else if space && matches!(self.peek(), Some(c) if c == '*' || c.is_ascii_alphabetic()) {
+                Combinator::Descendant
+            } else {
+                return Ok(Selector { steps });
+            };
+            steps.push(self.step(combinator, true)?);
+        }
+    }
+
+    fn step(&mut self, combinator: Combinator, capture: bool) -> Result<Step, Error> {
+        // The kind may be omitted before a filter, e.g. `:not(:has(x))`.
+        let kind = if self.eat("*") || matches!(self.peek(), Some('[') | Some(':')) {
+            None
+        } else {
+            let pos = self.pos;
+            let word = self.word();
+            if !word.starts_with(|c: char| c.is_ascii_uppercase()) {
+                self.pos = pos;
+                return Err(self.error("expected a node kind, e.g. `AlwaysConstruct`, or `*`"));
+            }
+            Some(word.to_string())
+        };
+
+        let mut filters = Vec::new();
+        loop {
+            if self.eat("[") {
+                filters.push(self.text_filter()?);
+            } else if self.eat(":has(") {
+                filters.push(Filter::Has(self.query()?));
+                self.expect(")")?;
+            } else if self.eat(":not(") {
+                self.skip_whitespace();
+                filters.push(Filter::Not(Box::new(self.step(Combinator::Descendant, false)?)));
+                self.expect(")")?;
+            } else {
+                break;
+            }
+        }
+
+        let capture = if capture && self.eat("@") {
+            let word = self.word();
+            if word.is_empty() {
+                return Err(self.error("expected a capture name"));
+            }
+            Some(word.to_string())
+        } else {
+            None
+        };
+
+        Ok(Step {
+            combinator,
+            kind,
+            filters,
+            capture,
+        })
+    }
+
+    fn text_filter(&mut self) -> Result<Filter, Error> {
+        self.skip_whitespace();
+        let pos = self.pos;
+        let attr = match self.word() {
+            "text" => Attr::Text,
+            "ident" => Attr::Ident,
+            _ => {
+                self.pos = pos;
+                return Err(self.error("expected `text` or `ident`"));
+            }
+        };
+
+        self.skip_whitespace();
+        let (regex, negated) = if self.eat("!~") {
+            (true, true)
+        } else if self.eat("!=") {
+            (false, true)
+        } else if self.eat("~") {
+            (true, false)
+        } else if self.eat("=") {
+            (false, false)
+        } else {
+            return Err(self.error("expected one of `=`, `!=`, `~`, `!~`"));
+        };
+
+        self.skip_whitespace();
+        let value = self.string()?;
+        let pattern = if regex {
+            Pattern::Regex(Regex::new(&value).map_err(|e| self.error(&format!("invalid regex: {}", e)))?)
+        } else {
+            Pattern::Equal(value)
+        };
+        self.expect("]")?;
+
+        Ok(Filter::Text {
+            attr,
+            pattern,
+            negated,
+        })
+    }
+
+    // Double-quoted string, where `\"` and `\\` are escaped.
+    fn string(&mut self) -> Result<String, Error> {
+        if !self.eat("\"") {
+            return Err(self.error("expected a double-quoted string"));
+        }
+        let mut ret = String::new();
+        let mut chars = self.s[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(ret);
+                }
+                '\\' => match chars.next() {
+                    Some((_, x)) if x == '"' || x == '\\' => ret.push(x),
+                    Some((_, x)) => {
+                        ret.push('\\');
+                        ret.push(x);
+                    }
+                    None => break,
+                },
+                x => ret.push(x),
+            }
+        }
+        self.pos = self.s.len();
+        Err(self.error("unterminated string"))
+    }
+}
@@ -1,6 +1,6 @@
 use crate::config::ConfigOption;
 use crate::linter::{SyntaxRule, SyntaxRuleResult};
-use sv_parser::{NodeEvent, RefNode, SyntaxTree};
+use sv_parser::{unwrap_locate, Locate, NodeEvent, RefNode, SyntaxTree};
 
 #[derive(Default)]
 pub struct EventlistOr;
@@ -8,7 +8,7 @@ pub struct EventlistOr;
 impl SyntaxRule for EventlistOr {
     fn check(
         &mut self,
-        _syntax_tree: &SyntaxTree,
+        syntax_tree: &SyntaxTree,
         event: &NodeEvent,
         _option: &ConfigOption,
     ) -> SyntaxRuleResult {
@@ -20,7 +20,27 @@ impl SyntaxRule for EventlistOr {
         };
 
         match node {
-            RefNode::EventExpressionOr(_) => SyntaxRuleResult::Fail,
+            RefNode::EventExpressionOr(x) => {
+                let (lhs, keyword, _) = &x.nodes;
+                let lhs_loc: &Locate = unwrap_locate!(lhs).unwrap();
+                let lhs_str: &str = syntax_tree.get_str(lhs).unwrap();
+                let lhs_trim: &str = syntax_tree.get_str_trim(lhs).unwrap();
+
+                // Replace the `or` keyword, and any whitespace preceding it,
+                // with a comma, unless there's a comment to preserve.
+                if lhs_str[lhs_trim.len()..].trim().is_empty() {
+                    let kw: &Locate = &keyword.nodes.0;
+                    let beg = lhs_loc.offset + lhs_trim.len();
+                    let fix = Locate {
+                        offset: beg,
+                        line: kw.line,
+                        len: kw.offset + kw.len - beg,
+                    };
+                    SyntaxRuleResult::FailFix(vec![(fix, String::from(","))])
+                } else {
+                    SyntaxRuleResult::Fail
+                }
+            }
             _ => SyntaxRuleResult::Pass,
         }
     }
@@ -20,7 +20,10 @@ impl SyntaxRule for KeywordForbiddenWireReg {
         };
         match node {
             RefNode::NetType(NetType::Wire(_)) => SyntaxRuleResult::Fail,
-            RefNode::IntegerVectorType(IntegerVectorType::Reg(_)) => SyntaxRuleResult::Fail,
+            RefNode::IntegerVectorType(IntegerVectorType::Reg(x)) => {
+                // Only `reg` has an exact replacement, as `logic` is a synonym.
+                SyntaxRuleResult::FailFix(vec![(x.nodes.0, String::from("logic"))])
+            }
             _ => SyntaxRuleResult::Pass,
         }
     }
@@ -1,14 +1,22 @@
 use crate::config::ConfigOption;
 use crate::linter::{SyntaxRule, SyntaxRuleResult};
-use sv_parser::{NodeEvent, RefNode, SyntaxTree};
+use sv_parser::{
+    unwrap_locate, ConstantExpression, ConstantPrimary, Expression, ForStepAssignment,
+    IncOrDecExpression, Locate, NodeEvent, Primary, RefNode, StatementItem, SyntaxTree,
+    VariableLvalue,
+};
 
 #[derive(Default)]
-pub struct OperatorIncdec;
+pub struct OperatorIncdec {
+    // Offset of the operator in a statement or for-loop step, and the fix
+    // which replaces that whole expression.
+    fix: Option<(usize, (Locate, String))>,
+}
 
 impl SyntaxRule for OperatorIncdec {
     fn check(
         &mut self,
-        _syntax_tree: &SyntaxTree,
+        syntax_tree: &SyntaxTree,
         event: &NodeEvent,
         _option: &ConfigOption,
     ) -> SyntaxRuleResult {
@@ -20,7 +28,25 @@ impl SyntaxRule for OperatorIncdec {
         };
 
         match node {
-            RefNode::IncOrDecOperator(_) => SyntaxRuleResult::Fail,
+            // Only where the expression's value isn't used, i.e. as a whole
+            // statement or for-loop step, is there an equivalent assignment.
+            RefNode::StatementItem(StatementItem::IncOrDecExpression(x)) => {
+                self.fix = incdec_fix(syntax_tree, &x.0);
+                SyntaxRuleResult::Pass
+            }
+            RefNode::ForStepAssignment(ForStepAssignment::IncOrDecExpression(x)) => {
+                self.fix = incdec_fix(syntax_tree, x);
+                SyntaxRuleResult::Pass
+            }
+            RefNode::IncOrDecOperator(x) => {
+                let op: &Locate = unwrap_locate!(*x).unwrap();
+                match self.fix.take() {
+                    Some((offset, fix)) if offset == op.offset => {
+                        SyntaxRuleResult::FailFix(vec![fix])
+                    }
+                    _ => SyntaxRuleResult::Fail,
+                }
+            }
             _ => SyntaxRuleResult::Pass,
         }
     }
@@ -37,3 +63,57 @@ impl SyntaxRule for OperatorIncdec {
         String::from("Only SystemVerilog, not Verilog, has increment and decrement operators.")
     }
 }
+
+// Replace `x++` or `++x` with `x = x + 1`, and similarly for `--`.
+fn incdec_fix(
+    syntax_tree: &SyntaxTree,
+    expression: &IncOrDecExpression,
+) -> Option<(usize, (Locate, String))> {
+    let (op, attributes, lvalue) = match expression {
+        IncOrDecExpression::Prefix(x) => (&x.nodes.0, &x.nodes.1, &x.nodes.2),
+        IncOrDecExpression::Suffix(x) => (&x.nodes.2, &x.nodes.1, &x.nodes.0),
+    };
+    if !attributes.is_empty() || !is_simple_lvalue(lvalue) {
+        return None;
+    }
+
+    let op_loc: &Locate = unwrap_locate!(op)?;
+    let op_str: &str = syntax_tree.get_str_trim(op)?;
+    let lvalue_str: &str = syntax_tree.get_str_trim(lvalue)?;
+
+    let loc: &Locate = unwrap_locate!(expression)?;
+    let fix = Locate {
+        offset: loc.offset,
+        line: loc.line,
+        len: syntax_tree.get_str_trim(expression)?.len(),
+    };
+    let text = format!("{0} = {0} {1} 1", lvalue_str, &op_str[..1]);
+
+    Some((op_loc.offset, (fix, text)))
+}
+
+// The fix writes the lvalue twice, so it must be evaluated without side
+// effects, e.g. `a` or `a[i]`, but not `a[f()]` or `a[i++]`.
+fn is_simple_lvalue(lvalue: &VariableLvalue) -> bool {
+    if !matches!(lvalue, VariableLvalue::Identifier(_)) {
+        return false;
+    }
+
+    lvalue.into_iter().all(|node| match node {
+        RefNode::Expression(Expression::Primary(x)) => {
+            matches!(**x, Primary::PrimaryLiteral(_) | Primary::Hierarchical(_))
+        }
+        RefNode::Expression(_) => false,
+        RefNode::ConstantExpression(ConstantExpression::ConstantPrimary(x)) => matches!(
+            **x,
+            ConstantPrimary::PrimaryLiteral(_)
+                | ConstantPrimary::PsParameter(_)
+                | ConstantPrimary::Specparam(_)
+                | ConstantPrimary::GenvarIdentifier(_)
+                | ConstantPrimary::FormalPort(_)
+                | ConstantPrimary::Enum(_)
+        ),
+        RefNode::ConstantExpression(_) => false,
+        _ => true,
+    })
+}
@@ -58,8 +58,8 @@ impl SyntaxRule for ReRequiredAssert {
             }
             RefNode::SimpleImmediateAssertStatement(_) |
             RefNode::DeferredImmediateAssertStatement(_) => {
-                match self.under_statement {
-                    Some(r) => r,
+                match &self.under_statement {
+                    Some(r) => r.clone(),
                     None => SyntaxRuleResult::Pass,
                 }
             }
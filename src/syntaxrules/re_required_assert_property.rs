@@ -62,9 +62,9 @@ impl SyntaxRule for ReRequiredAssertProperty {
 
         match node {
             RefNode::AssertPropertyStatement(_) => {
-                match (self.under_statement, self.under_concurrent_assertion_item_statement) {
-                    (Some(r), None) => r,
-                    (None, Some(r)) => r,
+                match (&self.under_statement, &self.under_concurrent_assertion_item_statement) {
+                    (Some(r), None) => r.clone(),
+                    (None, Some(r)) => r.clone(),
                     _ => SyntaxRuleResult::Pass,
                 }
             }
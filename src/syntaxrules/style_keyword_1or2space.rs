@@ -1,5 +1,5 @@
 use crate::config::ConfigOption;
-use crate::linter::{SyntaxRule, SyntaxRuleResult};
+use crate::linter::{fail_keyword_succ, SyntaxRule, SyntaxRuleResult};
 use regex::Regex;
 use sv_parser::{NodeEvent, RefNode, SyntaxTree};
 
@@ -59,7 +59,7 @@ impl SyntaxRule for StyleKeyword1Or2Space {
                     if re_succ.is_match(&caps[2]) {
                         SyntaxRuleResult::Pass
                     } else {
-                        SyntaxRuleResult::Fail
+                        fail_keyword_succ(x, syntax_tree, " ")
                     }
                 } else {
                     SyntaxRuleResult::Pass
@@ -1,12 +1,14 @@
 use crate::config::ConfigOption;
 use crate::linter::{SyntaxRule, SyntaxRuleResult};
 use regex::Regex;
-use sv_parser::{NodeEvent, RefNode, SyntaxTree, WhiteSpace};
+use sv_parser::{Locate, NodeEvent, RefNode, SyntaxTree, WhiteSpace};
 
 #[derive(Default)]
 pub struct StyleTrailingwhitespace {
     re: Option<Regex>,
     buffer: String,
+    trailing: Option<Locate>,
+    fixes: Vec<(Locate, String)>,
 }
 
 impl SyntaxRule for StyleTrailingwhitespace {
@@ -40,21 +42,21 @@ impl SyntaxRule for StyleTrailingwhitespace {
                     _ => {
                         // Clear the buffer on exit from Vec<WhiteSpace>.
                         self.buffer.clear();
+                        self.trailing = None;
                     }
                 }
 
                 // Append this node's string to buffer.
-                match n {
-                    RefNode::WhiteSpace(WhiteSpace::Space(x)) => {
-                        self.buffer.push_str(syntax_tree.get_str(x).unwrap());
-                    }
-                    RefNode::WhiteSpace(WhiteSpace::Newline(x)) => {
-                        self.buffer.push_str(syntax_tree.get_str(x).unwrap());
-                    }
-                    RefNode::WhiteSpace(WhiteSpace::Comment(x)) => {
-                        self.buffer.push_str(syntax_tree.get_str(x).unwrap());
-                    }
-                    _ => {}
+                let loc: Option<&Locate> = match n {
+                    RefNode::WhiteSpace(WhiteSpace::Space(x)) => Some(x),
+                    RefNode::WhiteSpace(WhiteSpace::Newline(x)) => Some(x),
+                    RefNode::WhiteSpace(WhiteSpace::Comment(x)) => Some(&x.nodes.0),
+                    _ => None,
+                };
+                if let Some(loc) = loc {
+                    let s: &str = syntax_tree.get_str(loc).unwrap();
+                    self.buffer.push_str(s);
+                    self.update_fixes(loc, s);
                 }
 
                 n
@@ -67,7 +69,7 @@ impl SyntaxRule for StyleTrailingwhitespace {
             RefNode::WhiteSpace(_) => {
                 let re = self.re.as_ref().unwrap();
                 if re.is_match(&self.buffer) {
-                    SyntaxRuleResult::Fail
+                    SyntaxRuleResult::FailFix(self.fixes.clone())
                 } else {
                     SyntaxRuleResult::Pass
                 }
@@ -88,3 +90,38 @@ impl SyntaxRule for StyleTrailingwhitespace {
         String::from("Trailing whitespace leads to unnecessary awkwardness with version control.")
     }
 }
+
+impl StyleTrailingwhitespace {
+    // Collect the spans of trailing whitespace which end at a newline in this
+    // element, and remember any whitespace it ends with for the next element.
+    fn update_fixes(&mut self, loc: &Locate, s: &str) {
+        self.fixes.clear();
+
+        if s.starts_with(['\n', '\x0b', '\x0c', '\r']) {
+            if let Some(trailing) = self.trailing {
+                self.fixes.push((trailing, String::new()));
+            }
+        }
+
+        let re = self.re.as_ref().unwrap();
+        for m in re.find_iter(s) {
+            let fix = Locate {
+                offset: loc.offset + m.start(),
+                line: loc.line,
+                len: m.len() - 1,
+            };
+            self.fixes.push((fix, String::new()));
+        }
+
+        let len = s.len() - s.trim_end_matches([' ', '\t']).len();
+        self.trailing = if len > 0 {
+            Some(Locate {
+                offset: loc.offset + s.len() - len,
+                line: loc.line,
+                len,
+            })
+        } else {
+            None
+        };
+    }
+}
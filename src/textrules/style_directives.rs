@@ -1,5 +1,5 @@
 use crate::config::ConfigOption;
-use crate::linter::{TextRule, TextRuleEvent, TextRuleResult};
+use crate::linter::{Fix, TextRule, TextRuleEvent, TextRuleResult};
 use regex::Regex;
 
 #[derive(Default)]
@@ -55,7 +55,18 @@ impl TextRule for StyleDirectives {
 
         if let Some(caps) = re.captures(line) {
             if let Some(m) = caps.get(1) {
-                if !re_comment.is_match(m.as_str()) {
+                if m.as_str().trim().is_empty() {
+                    // Only whitespace precedes the directive.
+                    return TextRuleResult::FailFix {
+                        offset: 0,
+                        len: m.as_str().chars().count(),
+                        fixes: vec![Fix {
+                            beg: 0,
+                            len: m.len(),
+                            text: String::new(),
+                        }],
+                    }
+                } else if !re_comment.is_match(m.as_str()) {
                     return TextRuleResult::Fail {
                         offset: 0,
                         len: m.as_str().chars().count(),
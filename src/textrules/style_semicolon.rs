@@ -1,10 +1,47 @@
 use crate::config::ConfigOption;
-use crate::linter::{TextRule, TextRuleEvent, TextRuleResult};
+use crate::linter::{Fix, TextRule, TextRuleEvent, TextRuleResult};
 use regex::Regex;
 
 #[derive(Default)]
 pub struct StyleSemicolon {
     re: Option<Regex>,
+    in_block_comment: bool, // Carried between lines.
+}
+
+impl StyleSemicolon {
+    // Whether each byte of the line is code, i.e. outside string literals
+    // and comments, where removing whitespace can't change its meaning.
+    fn code_mask(&mut self, line: &str) -> Vec<bool> {
+        let bytes = line.as_bytes();
+        let mut ret = vec![false; bytes.len()];
+        let mut in_string = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            if self.in_block_comment {
+                if bytes[i..].starts_with(b"*/") {
+                    self.in_block_comment = false;
+                    i += 1;
+                }
+            } else if in_string {
+                match bytes[i] {
+                    b'\\' => i += 1,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+            } else if bytes[i..].starts_with(b"//") {
+                break;
+            } else if bytes[i..].starts_with(b"/*") {
+                self.in_block_comment = true;
+                i += 1;
+            } else if bytes[i] == b'"' {
+                in_string = true;
+            } else {
+                ret[i] = true;
+            }
+            i += 1;
+        }
+        ret
+    }
 }
 
 impl TextRule for StyleSemicolon {
@@ -15,6 +52,7 @@ impl TextRule for StyleSemicolon {
     ) -> TextRuleResult {
         let line: &str = match event {
             TextRuleEvent::StartOfFile => {
+                self.in_block_comment = false;
                 return TextRuleResult::Pass;
             }
             TextRuleEvent::Line(x) => x,
@@ -23,13 +61,29 @@ impl TextRule for StyleSemicolon {
         if self.re.is_none() {
             self.re = Some(Regex::new("([ ]+);").unwrap());
         }
+        let code = self.code_mask(line);
         let re = self.re.as_ref().unwrap();
 
         if let Some(caps) = re.captures(line) {
             if let Some(m) = caps.get(1) {
-                TextRuleResult::Fail {
-                    offset: 0,
-                    len: m.as_str().chars().count(),
+                // Whitespace within strings and comments is reported, but only
+                // removed from code.
+                let fixes: Vec<Fix> = re
+                    .captures_iter(line)
+                    .filter_map(|caps| caps.get(0))
+                    .filter(|m| code[m.start()..m.end()].iter().all(|x| *x))
+                    .map(|m| Fix {
+                        beg: m.start(),
+                        len: m.len() - 1,
+                        text: String::new(),
+                    })
+                    .collect();
+                let offset = 0;
+                let len = m.as_str().chars().count();
+                if fixes.is_empty() {
+                    TextRuleResult::Fail { offset, len }
+                } else {
+                    TextRuleResult::FailFix { offset, len, fixes }
                 }
             } else {
                 TextRuleResult::Pass
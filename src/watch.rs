@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Changes to a set of files, found by polling the length and modification
+// time of each, which needs no support from the platform.
+// Missing files are watched too, so that creating them is seen.
+#[derive(Debug, Default)]
+pub struct Watcher {
+    stamps: HashMap<PathBuf, Option<(u64, SystemTime)>>,
+}
+
+impl Watcher {
+    // Replace the set of watched files.
+    // Files which were already watched keep their previous state, so that
+    // changes made while the set is being found aren't missed.
+    pub fn watch<I: IntoIterator<Item = PathBuf>>(&mut self, paths: I) {
+        let mut stamps = HashMap::new();
+        for path in paths {
+            let stamp = match self.stamps.remove(&path) {
+                Some(x) => x,
+                None => stamp(&path),
+            };
+            stamps.insert(path, stamp);
+        }
+        self.stamps = stamps;
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.stamps.keys()
+    }
+
+    // Files which have changed since they were last seen, in no particular
+    // order.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let mut ret = Vec::new();
+        for (path, x) in self.stamps.iter_mut() {
+            let new = stamp(path);
+            if *x != new {
+                *x = new;
+                ret.push(path.clone());
+            }
+        }
+        ret
+    }
+
+    // Block until any file changes.
+    // Editors often write a file in several steps, so changes are collected
+    // until there have been none for one interval.
+    pub fn wait(&mut self, interval: Duration) -> Vec<PathBuf> {
+        let mut ret = Vec::new();
+        loop {
+            thread::sleep(interval);
+            let changed = self.changed();
+            if changed.is_empty() && !ret.is_empty() {
+                return ret;
+            }
+            ret.extend(changed);
+        }
+    }
+}
+
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
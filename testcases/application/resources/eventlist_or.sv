@@ -0,0 +1,9 @@
+module M
+  ( input  var logic i_clk
+  , input  var logic i_arst
+  , output var logic o_q
+  );
+  always_ff @(posedge i_clk or posedge i_arst)
+    if (i_arst) o_q <= 1'b0;
+    else        o_q <= 1'b1;
+endmodule
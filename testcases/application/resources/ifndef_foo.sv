@@ -0,0 +1,5 @@
+module M;
+`ifndef SVLINT_DEFINE_FOO
+  assign = 1;
+`endif
+endmodule
@@ -0,0 +1,3 @@
+module M;
+  assign = 1;
+endmodule
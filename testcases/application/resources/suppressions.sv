@@ -0,0 +1,10 @@
+module M;
+  // svlint off eventlist_orr
+  // svlint off eventlist_or
+  always_ff @(posedge i_clk or posedge i_arst) o_q <= 1'b0;
+  // svlint on eventlist_or
+  // svlint off case_default
+  always_comb a = b;
+  // svlint on case_default
+  always_comb c = d; // svlint ignore style_textwidth
+endmodule
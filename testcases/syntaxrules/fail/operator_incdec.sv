@@ -27,3 +27,7 @@ module M;
     assign z[i] = y[i] + x[i];
   end
 endmodule
+////////////////////////////////////////////////////////////////////////////////
+module M;
+  always @(posedge clk) z[f(i)]++;
+endmodule
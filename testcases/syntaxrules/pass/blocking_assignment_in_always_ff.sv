@@ -5,3 +5,18 @@ module M;
   always_ff @(posedge clk) q2 = d;  // Control comments avoid failure.
   /* svlint on blocking_assignment_in_always_ff */
 endmodule
+////////////////////////////////////////////////////////////////////////////////
+module M;
+  // svlint off blocking_assignment_in_always_ff
+  always_ff @(posedge clk) q = d;   // Line comments may also be used.
+  // svlint on blocking_assignment_in_always_ff
+endmodule
+////////////////////////////////////////////////////////////////////////////////
+module M;
+  always_ff @(posedge clk) q1 = d; // svlint ignore blocking_assignment_in_always_ff
+
+  // svlint off-next-line blocking_assignment_in_always_ff, style_indent
+  always_ff @(posedge clk) q2 = d;
+
+  always_ff @(posedge clk) q3 = d; /* svlint ignore all */
+endmodule
@@ -8,3 +8,11 @@ consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse
 cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non
 proident, sunt in culpa qui officia deserunt mollit anim id est laborum.
 */
+////////////////////////////////////////////////////////////////////////////////
+/* svlint off style_textwidth */
+localparam bit [127:0] TABLE = 128'h0123456789ABCDEF_0123456789ABCDEF_0123456789ABCDEF_0123456789ABCDEF;
+/* svlint on style_textwidth */
+////////////////////////////////////////////////////////////////////////////////
+// svlint off-next-line style_textwidth
+localparam bit [127:0] A = 128'h0123456789ABCDEF_0123456789ABCDEF_0123456789ABCDEF_0123456789ABCDEF;
+localparam bit [127:0] B = 128'h0123456789ABCDEF_0123456789ABCDEF; // svlint ignore all